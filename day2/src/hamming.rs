@@ -0,0 +1,209 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::error;
+use std::fmt;
+use std::hash::Hash;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    LengthMismatch {
+        index: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::LengthMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "id #{} has length {}, expected {}",
+                index, found, expected
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Two ids which differ in at most `k` positions, and the elements they have in common.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Match<T> {
+    pub lhs: usize,
+    pub rhs: usize,
+    pub distance: usize,
+    pub common: Vec<T>,
+}
+
+// Calls `f` for every set of `k` positions out of `0..len`, in lexicographic order
+fn for_each_combination<F: FnMut(&[usize])>(len: usize, k: usize, mut f: F) {
+    if k > len {
+        return;
+    }
+
+    let mut comb: Vec<usize> = (0..k).collect();
+    loop {
+        f(&comb);
+
+        let mut i = k;
+        while i > 0 && comb[i - 1] == len - k + i - 1 {
+            i -= 1;
+        }
+        if i == 0 {
+            return;
+        }
+        comb[i - 1] += 1;
+        for j in i..k {
+            comb[j] = comb[j - 1] + 1;
+        }
+    }
+}
+
+/// Finds all pairs of ids that differ in at most `k` positions.
+///
+/// Every id is filed under one key per set of `k` positions, made of these positions and the id
+/// with them deleted. Two ids share a key exactly if they agree everywhere except on at most
+/// those `k` positions, so only ids with a common key are ever compared. For a fixed `k`, this
+/// takes time linear in the number of ids.
+///
+/// All ids must have the same length, which is taken to be the most common one, or the one seen
+/// first among equally common lengths. Ids of other lengths are reported as errors and skipped.
+pub fn near_duplicates<T>(ids: &[&[T]], k: usize) -> (Vec<Match<T>>, Vec<Error>)
+where
+    T: Eq + Clone + Hash,
+{
+    let mut errors = Vec::new();
+    // Number of ids and index of the first id for every length
+    let mut lengths: HashMap<usize, (usize, usize)> = HashMap::new();
+    for (index, id) in ids.iter().enumerate() {
+        lengths.entry(id.len()).or_insert((0, index)).0 += 1;
+    }
+    let len = match lengths
+        .iter()
+        .max_by_key(|(_, &(count, first))| (count, Reverse(first)))
+    {
+        Some((&len, _)) => len,
+        None => return (Vec::new(), errors),
+    };
+
+    let mut buckets: HashMap<(Vec<usize>, Vec<T>), Vec<usize>> = HashMap::new();
+    for (index, id) in ids.iter().enumerate() {
+        if id.len() != len {
+            errors.push(Error::LengthMismatch {
+                index,
+                expected: len,
+                found: id.len(),
+            });
+            continue;
+        }
+
+        for_each_combination(len, k.min(len), |positions| {
+            let mut rest = Vec::with_capacity(len - positions.len());
+            let mut deleted = positions.iter().peekable();
+            for (pos, el) in id.iter().enumerate() {
+                if deleted.peek() == Some(&&pos) {
+                    deleted.next();
+                } else {
+                    rest.push(el.clone());
+                }
+            }
+            buckets
+                .entry((positions.to_vec(), rest))
+                .or_default()
+                .push(index);
+        });
+    }
+
+    let mut seen = HashSet::new();
+    let mut matches = Vec::new();
+    for bucket in buckets.values() {
+        for (i, &lhs) in bucket.iter().enumerate() {
+            for &rhs in &bucket[i + 1..] {
+                if !seen.insert((lhs, rhs)) {
+                    continue;
+                }
+
+                let common: Vec<T> = ids[lhs]
+                    .iter()
+                    .zip(ids[rhs].iter())
+                    .filter(|(a, b)| a == b)
+                    .map(|(a, _)| a.clone())
+                    .collect();
+                matches.push(Match {
+                    lhs,
+                    rhs,
+                    distance: len - common.len(),
+                    common,
+                });
+            }
+        }
+    }
+    matches.sort_by_key(|m| (m.lhs, m.rhs));
+
+    (matches, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let ids: Vec<&[u8]> = vec![
+            b"abcde", b"fghij", b"klmno", b"pqrst", b"fguij", b"axcye", b"wvxyz",
+        ];
+
+        let (matches, errors) = near_duplicates(&ids, 1);
+        assert!(errors.is_empty());
+        assert_eq!(
+            matches,
+            vec![Match {
+                lhs: 1,
+                rhs: 4,
+                distance: 1,
+                common: b"fgij".to_vec(),
+            }]
+        );
+
+        let (matches, _) = near_duplicates(&ids, 2);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].common, b"ace".to_vec());
+    }
+
+    #[test]
+    fn length_mismatch() {
+        let ids: Vec<&[u8]> = vec![b"abc", b"abd", b"ab", b"xbd"];
+
+        let (matches, errors) = near_duplicates(&ids, 1);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(
+            errors,
+            vec![Error::LengthMismatch {
+                index: 2,
+                expected: 3,
+                found: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn odd_length_first() {
+        let ids: Vec<&[u8]> = vec![b"ab", b"abc", b"abd", b"xbd"];
+
+        let (matches, errors) = near_duplicates(&ids, 1);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(
+            errors,
+            vec![Error::LengthMismatch {
+                index: 0,
+                expected: 3,
+                found: 2,
+            }]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Number of occurrences of every element of an id.
+pub struct Histogram<T> {
+    counts: HashMap<T, usize>,
+}
+
+impl<T: Eq + Hash + Clone> Histogram<T> {
+    pub fn new(els: &[T]) -> Self {
+        let mut counts = HashMap::new();
+        for el in els {
+            *counts.entry(el.clone()).or_insert(0) += 1;
+        }
+        Histogram { counts }
+    }
+
+    #[allow(dead_code)]
+    pub fn count(&self, el: &T) -> usize {
+        self.counts.get(el).cloned().unwrap_or(0)
+    }
+
+    /// Maps every multiplicity to the number of elements that occur exactly that often.
+    pub fn profile(&self) -> BTreeMap<usize, usize> {
+        let mut profile = BTreeMap::new();
+        for n in self.counts.values() {
+            *profile.entry(*n).or_insert(0) += 1;
+        }
+        profile
+    }
+
+    pub fn has_multiplicity(&self, n: usize) -> bool {
+        self.counts.values().any(|c| *c == n)
+    }
+}
+
+/// For every multiplicity n, the number of ids that contain some element exactly n times.
+pub struct Checksum {
+    pub classes: BTreeMap<usize, usize>,
+}
+
+impl Checksum {
+    pub fn new<T: Eq + Hash + Clone>(ids: &[&[T]], multiplicities: &[usize]) -> Self {
+        let mut classes: BTreeMap<usize, usize> = multiplicities.iter().map(|n| (*n, 0)).collect();
+        for id in ids {
+            let hist = Histogram::new(id);
+            for (n, cnt) in classes.iter_mut() {
+                if hist.has_multiplicity(*n) {
+                    *cnt += 1;
+                }
+            }
+        }
+        Checksum { classes }
+    }
+
+    pub fn value(&self) -> usize {
+        self.classes.values().product()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let ids: Vec<&[u8]> = vec![
+            b"abcdef", b"bababc", b"abbcde", b"abcccd", b"aabcdd", b"abcdee", b"ababab",
+        ];
+
+        let checksum = Checksum::new(&ids, &[2, 3]);
+        assert_eq!(checksum.classes[&2], 4);
+        assert_eq!(checksum.classes[&3], 3);
+        assert_eq!(checksum.value(), 12);
+
+        let hist = Histogram::new(b"bababc");
+        assert_eq!(hist.count(&b'b'), 3);
+        assert_eq!(
+            hist.profile().into_iter().collect::<Vec<_>>(),
+            vec![(1, 1), (2, 1), (3, 1)]
+        );
+    }
+}
@@ -1,66 +1,47 @@
-use std::clone::Clone;
-use std::cmp::PartialEq;
-use std::fs;
-use std::str;
-
-fn contains_dup_n<T: PartialEq>(n: usize, els: &[T]) -> bool {
-    for el in els {
-        if els.iter().filter(|&x| x == el).count() == n {
-            return true;
-        }
-    }
-    false
-}
+mod hamming;
+mod histogram;
 
-fn distance<T: PartialEq>(lhs: &[T], rhs: &[T]) -> usize {
-    assert_eq!(lhs.len(), rhs.len());
-    let mut distance = 0;
-    for idx in 0..lhs.len() {
-        if lhs[idx] != rhs[idx] {
-            distance += 1;
-        }
-    }
-    distance
-}
+use histogram::{Checksum, Histogram};
 
-fn intersect<T: PartialEq + Clone>(lhs: &[T], rhs: &[T]) -> Vec<T> {
-    assert_eq!(lhs.len(), rhs.len());
-    let mut result = Vec::new();
-    for idx in 0..lhs.len() {
-        if lhs[idx] == rhs[idx] {
-            result.push(lhs[idx].clone())
-        }
-    }
-    result
-}
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::str;
 
 fn main() {
-    let mut doubles = 0;
-    let mut triples = 0;
-    let mut intersection = Vec::new();
-
     let input = fs::read("input").unwrap();
     let ids: Vec<_> = input
         .split(|&c| c as char == '\n')
         .filter(|v| !v.is_empty())
         .collect();
 
-    for (idx, id) in ids.iter().enumerate() {
-        if contains_dup_n(2, id) {
-            doubles += 1;
-        }
-        if contains_dup_n(3, id) {
-            triples += 1;
+    let checksum = Checksum::new(&ids, &[2, 3]);
+    println!("Checksum: {}", checksum.value());
+
+    if env::args().any(|a| a == "--report") {
+        for (n, cnt) in &checksum.classes {
+            println!("  {} ids contain some letter exactly {} times", cnt, n);
         }
 
-        for other in &ids[idx..] {
-            if distance(id, other) == 1 {
-                intersection = intersect(id, other);
-                break;
-            }
+        let mut profiles: BTreeMap<Vec<(usize, usize)>, usize> = BTreeMap::new();
+        for id in &ids {
+            let profile = Histogram::new(id).profile().into_iter().collect();
+            *profiles.entry(profile).or_insert(0) += 1;
+        }
+        for (profile, cnt) in profiles {
+            let profile: Vec<_> = profile
+                .iter()
+                .map(|(n, letters)| format!("{}x{}", letters, n))
+                .collect();
+            println!("  {} ids with letter counts {}", cnt, profile.join(", "));
         }
     }
 
-    println!("Checksum: {}", doubles * triples);
-    println!("Intersection: {}", str::from_utf8(&intersection).unwrap());
+    let (matches, errors) = hamming::near_duplicates(&ids, 1);
+    for err in errors {
+        eprintln!("Skipping {}", err);
+    }
+    for m in matches {
+        println!("Intersection: {}", str::from_utf8(&m.common).unwrap());
+    }
 }
@@ -1,12 +1,15 @@
+mod render;
 mod util;
 
-use crate::util::Point;
+use crate::util::{Grid, Point};
 
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::env;
 use std::fs;
 use std::iter;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Tool {
     Neither,
     Torch,
@@ -25,9 +28,17 @@ impl Tool {
     fn iter() -> impl Iterator<Item = Tool> {
         vec![Tool::Neither, Tool::Torch, Tool::ClimbingGear].into_iter()
     }
+
+    fn as_char(self) -> char {
+        match self {
+            Tool::Neither => 'n',
+            Tool::Torch => 't',
+            Tool::ClimbingGear => 'g',
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 enum RegionType {
     Rocky,
     Wet,
@@ -36,148 +47,252 @@ enum RegionType {
 
 impl RegionType {
     fn visitable_with(&self, tool: Tool) -> bool {
-        match (self, tool) {
+        !matches!(
+            (self, tool),
             (RegionType::Rocky, Tool::Neither)
-            | (RegionType::Wet, Tool::Torch)
-            | (RegionType::Narrow, Tool::ClimbingGear) => false,
-            _ => true,
-        }
+                | (RegionType::Wet, Tool::Torch)
+                | (RegionType::Narrow, Tool::ClimbingGear)
+        )
     }
 }
 
+/// Time it takes to move one region, and to switch tools.
+#[derive(Debug, Clone, Copy)]
+struct Costs {
+    step: u32,
+    switch: u32,
+}
+
+impl Default for Costs {
+    fn default() -> Self {
+        Costs { step: 1, switch: 7 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Move {
+    Step(Point),
+    Switch(Tool),
+}
+
+/// A rescue route, as the sequence of (position, equipped tool) states from the mouth of the
+/// cave to the target.
 #[derive(Debug, Clone)]
-struct Region {
+struct Route {
+    time: u32,
+    states: Vec<(Point, Tool)>,
+}
+
+impl Route {
+    fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.states.windows(2).map(|w| {
+            if w[0].0 != w[1].0 {
+                Move::Step(w[1].0)
+            } else {
+                Move::Switch(w[1].1)
+            }
+        })
+    }
+
+    fn tool_at(&self, pos: Point) -> Option<Tool> {
+        self.states.iter().find(|s| s.0 == pos).map(|s| s.1)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct State {
+    estimate: u32,
+    time: u32,
     pos: Point,
-    geo: u64,
-    erosion: u64,
-    typ: RegionType,
+    tool: Tool,
+}
+
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so that the `BinaryHeap` pops the lowest estimate first
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.time.cmp(&other.time))
+    }
+}
 
-    dists: [u32; 3],
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 #[derive(Debug)]
 struct Cave {
     depth: u64,
     target: Point,
-    regions: HashMap<Point, Region>,
+    erosion: Grid<u64>,
+    dists: Grid<[u32; 3]>,
 }
 
 impl Cave {
     fn new(depth: u64, target: Point) -> Self {
-        Cave {
+        let mut cave = Cave {
             depth,
             target,
-            regions: HashMap::new(),
-        }
+            erosion: Grid::new(0, 0, 0),
+            dists: Grid::new(0, 0, [u32::MAX; 3]),
+        };
+        cave.ensure(target);
+        cave
     }
 
-    fn region(&mut self, pos: Point) -> &mut Region {
-        if !self.regions.contains_key(&pos) {
-            let geo = self.calc_geo(pos);
-            let erosion = (geo + self.depth) % 20183;
-            let typ = match erosion % 3 {
-                0 => RegionType::Rocky,
-                1 => RegionType::Wet,
-                2 => RegionType::Narrow,
-                _ => panic!(),
-            };
-
-            self.regions.insert(
-                pos,
-                Region {
-                    pos,
-                    geo,
-                    erosion,
-                    typ,
-
-                    dists: [u32::max_value(); 3],
-                },
-            );
+    /// Grows the grids to include `pos`, computing the erosion levels of all new regions row by
+    /// row.
+    fn ensure(&mut self, pos: Point) {
+        if self.erosion.contains(pos) {
+            return;
         }
 
-        self.regions.get_mut(&pos).unwrap()
+        let (old_width, old_height) = (self.erosion.width(), self.erosion.height());
+        let width = if pos.x < old_width {
+            old_width
+        } else {
+            (pos.x + 1).max(old_width * 2)
+        };
+        let height = if pos.y < old_height {
+            old_height
+        } else {
+            (pos.y + 1).max(old_height * 2)
+        };
+
+        self.erosion.resize(width, height, 0);
+        self.dists.resize(width, height, [u32::MAX; 3]);
+
+        for y in 0..height {
+            let x_start = if y < old_height { old_width } else { 0 };
+            for x in x_start..width {
+                let p = Point::new(x, y);
+                let geo = if p == Point::new(0, 0) || p == self.target {
+                    0
+                } else if y == 0 {
+                    u64::from(x) * 16807
+                } else if x == 0 {
+                    u64::from(y) * 48271
+                } else {
+                    self.erosion[Point::new(x - 1, y)] * self.erosion[Point::new(x, y - 1)]
+                };
+                self.erosion[p] = (geo + self.depth) % 20183;
+            }
+        }
     }
 
-    fn erosion(&mut self, pos: Point) -> u64 {
-        self.region(pos).erosion
+    fn region(&mut self, pos: Point) -> RegionType {
+        self.ensure(pos);
+        match self.erosion[pos] % 3 {
+            0 => RegionType::Rocky,
+            1 => RegionType::Wet,
+            2 => RegionType::Narrow,
+            _ => unreachable!(),
+        }
+    }
+
+    fn dists(&mut self, pos: Point) -> &mut [u32; 3] {
+        self.ensure(pos);
+        &mut self.dists[pos]
     }
 
     fn danger(&mut self, pos: Point) -> u64 {
-        match self.region(pos).typ {
+        match self.region(pos) {
             RegionType::Rocky => 0,
             RegionType::Wet => 1,
             RegionType::Narrow => 2,
         }
     }
 
-    fn calc_geo(&mut self, pos: Point) -> u64 {
-        if pos == Point::new(0, 0) || pos == self.target {
-            return 0;
-        }
-
-        match (pos.x, pos.y) {
-            (0, y) => u64::from(y * 48271),
-            (x, 0) => u64::from(x * 16807),
-            (x, y) => {
-                (self.erosion(Point::new(x - 1, y)) * self.erosion(Point::new(x, y - 1))) % 20183
-            }
+    /// Lower bound on the time left to reach the target with the torch equipped.
+    fn heuristic(&self, pos: Point, tool: Tool, costs: Costs) -> u32 {
+        let dist = pos.manhattan(self.target) * costs.step;
+        if tool == Tool::Torch {
+            dist
+        } else {
+            dist + costs.switch
         }
     }
 
-    fn find_min_dist(&mut self) -> u32 {
-        let mut frontier: Vec<(u32, Point, Tool)> = vec![(0, Point::new(0, 0), Tool::Torch)];
-
-        let mut time = 0;
-        'outer: loop {
-            let mut new_frontier = frontier.clone();
+    /// A* search over (position, tool) states.
+    fn find_route(&mut self, costs: Costs) -> Option<Route> {
+        self.dists.fill([u32::MAX; 3]);
+
+        let start = (Point::new(0, 0), Tool::Torch);
+        let mut prev: HashMap<(Point, Tool), (Point, Tool)> = HashMap::new();
+        let mut frontier = BinaryHeap::new();
+
+        self.dists(start.0)[start.1.as_idx()] = 0;
+        frontier.push(State {
+            estimate: self.heuristic(start.0, start.1, costs),
+            time: 0,
+            pos: start.0,
+            tool: start.1,
+        });
+
+        while let Some(State {
+            time, pos, tool, ..
+        }) = frontier.pop()
+        {
+            if time > self.dists(pos)[tool.as_idx()] {
+                continue;
+            }
 
-            for idx in (0..frontier.len()).rev() {
-                let cand = &frontier[idx];
+            if (pos, tool) == (self.target, Tool::Torch) {
+                let mut states = vec![(pos, tool)];
+                while let Some(&s) = prev.get(states.last().unwrap()) {
+                    states.push(s);
+                }
+                states.reverse();
 
-                if cand.0 == time {
-                    new_frontier.remove(idx);
+                return Some(Route { time, states });
+            }
 
-                    if (cand.1, cand.2) == (self.target, Tool::Torch) {
-                        break 'outer;
-                    }
+            let switches = Tool::iter()
+                .filter(|&t| t != tool)
+                .map(|t| (pos, t, costs.switch))
+                .collect::<Vec<_>>();
+            let steps = pos.nb_iter().map(|p| (p, tool, costs.step));
 
-                    for pos in cand.1.nb_iter() {
-                        for tool in Tool::iter() {
-                            if !self.region(pos).typ.visitable_with(tool)
-                                || !self.region(cand.1).typ.visitable_with(tool)
-                            {
-                                continue;
-                            }
+            for (p, t, cost) in switches.into_iter().chain(steps) {
+                if !self.region(p).visitable_with(t) {
+                    continue;
+                }
 
-                            let d = if tool == cand.2 { time + 1 } else { time + 8 };
-                            if d < self.region(pos).dists[tool.as_idx()] {
-                                self.region(pos).dists[tool.as_idx()] = d;
-                                new_frontier.push((d, pos, tool));
-                            }
-                        }
-                    }
+                let d = time + cost;
+                if d < self.dists(p)[t.as_idx()] {
+                    self.dists(p)[t.as_idx()] = d;
+                    prev.insert((p, t), (pos, tool));
+                    frontier.push(State {
+                        estimate: d + self.heuristic(p, t, costs),
+                        time: d,
+                        pos: p,
+                        tool: t,
+                    });
                 }
             }
-
-            time += 1;
-            frontier = new_frontier;
         }
 
-        time
+        None
     }
 
+    /// Prints the map, with the regions along `route` marked by the tool used there.
     #[allow(dead_code)]
-    fn print(&mut self, size: u32) {
+    fn print(&mut self, size: u32, route: Option<&Route>) {
         for y in 0..=size {
             for x in 0..=size {
                 if x == 0 && y == 0 {
                     print!("M");
                 } else if Point::new(x, y) == self.target {
                     print!("T");
+                } else if let Some(tool) = route.and_then(|r| r.tool_at(Point::new(x, y))) {
+                    print!("{}", tool.as_char());
                 } else {
                     print!(
                         "{}",
-                        match self.region(Point::new(x, y)).typ {
+                        match self.region(Point::new(x, y)) {
                             RegionType::Rocky => '.',
                             RegionType::Wet => '=',
                             RegionType::Narrow => '|',
@@ -190,7 +305,7 @@ impl Cave {
     }
 }
 
-fn main() -> Result<(), Box<std::error::Error>> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let input = fs::read_to_string("input")?;
 
     let (depth, target) = {
@@ -199,22 +314,22 @@ fn main() -> Result<(), Box<std::error::Error>> {
         let l = lines.next().expect("invalid input");
         let depth: u64 = l
             .chars()
-            .skip_while(|c| !c.is_digit(10))
+            .skip_while(|c| !c.is_ascii_digit())
             .collect::<String>()
             .parse()?;
 
         let l = lines.next().expect("invalid input");
         let x = l
             .chars()
-            .skip_while(|c| !c.is_digit(10))
-            .take_while(|c| c.is_digit(10))
+            .skip_while(|c| !c.is_ascii_digit())
+            .take_while(|c| c.is_ascii_digit())
             .collect::<String>()
             .parse()?;
         let y = l
             .chars()
             .skip_while(|c| *c != ',')
             .skip(1)
-            .take_while(|c| c.is_digit(10))
+            .take_while(|c| c.is_ascii_digit())
             .collect::<String>()
             .parse()?;
 
@@ -228,7 +343,64 @@ fn main() -> Result<(), Box<std::error::Error>> {
         .sum();
     println!("Part 1: {}", danger);
 
-    println!("Part 2: {}", cave.find_min_dist());
+    let route = cave
+        .find_route(Costs::default())
+        .expect("no route to target");
+    println!("Part 2: {}", route.time);
+
+    if env::args().any(|a| a == "--render") {
+        let corner = route
+            .states
+            .iter()
+            .fold(target, |c, (p, _)| Point::new(c.x.max(p.x), c.y.max(p.y)));
+
+        cave.render_risk(4).write_ppm("risk.ppm")?;
+        let mut img = cave.render_regions(corner, 4);
+        img.write_ppm("regions.ppm")?;
+        img.overlay(&route);
+        img.write_ppm("route.ppm")?;
+    }
+    if env::args().any(|a| a == "--ansi") {
+        cave.print_ansi(target, Some(&route));
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let mut cave = Cave::new(510, Point::new(10, 10));
+        let route = cave.find_route(Costs::default()).expect("no route");
+
+        assert_eq!(route.time, 45);
+        let switches = route
+            .moves()
+            .filter(|m| matches!(m, Move::Switch(_)))
+            .count() as u32;
+        let steps = route.moves().count() as u32 - switches;
+        assert_eq!(steps + 7 * switches, route.time);
+        assert_eq!(
+            route.states.last(),
+            Some(&(Point::new(10, 10), Tool::Torch))
+        );
+    }
+
+    #[test]
+    fn far_target() {
+        let mut cave = Cave::new(510, Point::new(10, 10));
+        assert_eq!(cave.erosion[Point::new(10, 10)], 510);
+
+        cave.ensure(Point::new(40_000, 3));
+        assert_eq!(cave.erosion.width(), 40_001);
+        assert_eq!(cave.erosion.height(), 11);
+        assert_eq!(cave.erosion[Point::new(10, 10)], 510);
+        assert_eq!(
+            cave.erosion[Point::new(40_000, 0)],
+            (40_000 * 16807 + 510) % 20183
+        );
+    }
+}
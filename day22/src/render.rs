@@ -0,0 +1,155 @@
+use crate::util::Point;
+use crate::{Cave, Move, RegionType, Route};
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+type Rgb = [u8; 3];
+
+const MOUTH: Rgb = [0, 200, 0];
+const TARGET: Rgb = [255, 0, 0];
+const PATH: Rgb = [255, 220, 0];
+const SWITCH: Rgb = [255, 0, 255];
+
+fn region_color(typ: RegionType) -> Rgb {
+    match typ {
+        RegionType::Rocky => [120, 120, 120],
+        RegionType::Wet => [40, 90, 200],
+        RegionType::Narrow => [110, 70, 30],
+    }
+}
+
+fn risk_color(risk: u64) -> Rgb {
+    match risk {
+        0 => [30, 30, 30],
+        1 => [230, 130, 20],
+        _ => [200, 20, 20],
+    }
+}
+
+fn region_ansi(typ: RegionType) -> u8 {
+    match typ {
+        RegionType::Rocky => 100,
+        RegionType::Wet => 44,
+        RegionType::Narrow => 43,
+    }
+}
+
+/// An RGB image, in which every region of the cave covers `scale`×`scale` pixels.
+pub struct Image {
+    width: u32,
+    height: u32,
+    scale: u32,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    fn new(width: u32, height: u32, scale: u32) -> Self {
+        Image {
+            width: width * scale,
+            height: height * scale,
+            scale,
+            pixels: vec![[0; 3]; (width * scale * height * scale) as usize],
+        }
+    }
+
+    fn set(&mut self, pos: Point, color: Rgb) {
+        for y in pos.y * self.scale..(pos.y + 1) * self.scale {
+            for x in pos.x * self.scale..(pos.x + 1) * self.scale {
+                if x < self.width && y < self.height {
+                    self.pixels[(x + y * self.width) as usize] = color;
+                }
+            }
+        }
+    }
+
+    /// Draws `route` on top of the image, marking the regions where tools are switched.
+    pub fn overlay(&mut self, route: &Route) {
+        for (pos, _) in &route.states {
+            self.set(*pos, PATH);
+        }
+
+        let mut pos = route.states[0].0;
+        for m in route.moves() {
+            match m {
+                Move::Step(p) => pos = p,
+                Move::Switch(_) => self.set(pos, SWITCH),
+            }
+        }
+    }
+
+    pub fn write_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut out = io::BufWriter::new(fs::File::create(path)?);
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for px in &self.pixels {
+            out.write_all(px)?;
+        }
+        out.flush()
+    }
+}
+
+impl Cave {
+    /// Renders the region types of the cave, from the mouth to `corner`.
+    pub fn render_regions(&mut self, corner: Point, scale: u32) -> Image {
+        let mut img = Image::new(corner.x + 1, corner.y + 1, scale);
+        for y in 0..=corner.y {
+            for x in 0..=corner.x {
+                let pos = Point::new(x, y);
+                img.set(pos, region_color(self.region(pos)));
+            }
+        }
+        img.set(Point::new(0, 0), MOUTH);
+        img.set(self.target, TARGET);
+
+        img
+    }
+
+    /// Renders the risk level of every region in the rectangle between the mouth and the target.
+    pub fn render_risk(&mut self, scale: u32) -> Image {
+        let target = self.target;
+        let mut img = Image::new(target.x + 1, target.y + 1, scale);
+        for y in 0..=target.y {
+            for x in 0..=target.x {
+                let pos = Point::new(x, y);
+                img.set(pos, risk_color(self.danger(pos)));
+            }
+        }
+
+        img
+    }
+
+    /// Like `print`, but colors the regions and highlights the route, using ANSI escapes.
+    pub fn print_ansi(&mut self, corner: Point, route: Option<&Route>) {
+        let mut switches = Vec::new();
+        if let Some(route) = route {
+            let mut pos = route.states[0].0;
+            for m in route.moves() {
+                match m {
+                    Move::Step(p) => pos = p,
+                    Move::Switch(_) => switches.push(pos),
+                }
+            }
+        }
+
+        for y in 0..=corner.y {
+            for x in 0..=corner.x {
+                let pos = Point::new(x, y);
+                let bg = region_ansi(self.region(pos));
+                let c = if pos == Point::new(0, 0) {
+                    'M'
+                } else if pos == self.target {
+                    'T'
+                } else if switches.contains(&pos) {
+                    '*'
+                } else if let Some(tool) = route.and_then(|r| r.tool_at(pos)) {
+                    tool.as_char()
+                } else {
+                    ' '
+                };
+                print!("\x1b[{};97m{}", bg, c);
+            }
+            println!("\x1b[0m");
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use std::fmt;
-use std::ops::{Add, AddAssign};
+use std::ops::{Add, AddAssign, Index, IndexMut};
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
 pub struct Point {
@@ -12,6 +12,11 @@ impl Point {
         Point { x, y }
     }
 
+    pub fn manhattan(self, other: Point) -> u32 {
+        (i64::from(self.x) - i64::from(other.x)).unsigned_abs() as u32
+            + (i64::from(self.y) - i64::from(other.y)).unsigned_abs() as u32
+    }
+
     pub fn nb_iter(self) -> impl Iterator<Item = Point> {
         let mut ns = Vec::with_capacity(4);
         if self.x > 0 {
@@ -48,3 +53,78 @@ impl fmt::Debug for Point {
         write!(f, "({}, {})", self.x, self.y)
     }
 }
+
+/// A dense 2D array indexed by `Point`, which can grow in either direction.
+#[derive(Debug, Clone)]
+pub struct Grid<T> {
+    width: u32,
+    height: u32,
+    buf: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: u32, height: u32, fill: T) -> Self {
+        Grid {
+            width,
+            height,
+            buf: vec![fill; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        p.x < self.width && p.y < self.height
+    }
+
+    pub fn fill(&mut self, value: T) {
+        for v in &mut self.buf {
+            *v = value.clone();
+        }
+    }
+
+    /// Resizes the grid, keeping existing values in place and filling new cells with `fill`.
+    pub fn resize(&mut self, width: u32, height: u32, fill: T) {
+        let mut buf = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let p = Point::new(x, y);
+                if self.contains(p) {
+                    buf.push(self[p].clone());
+                } else {
+                    buf.push(fill.clone());
+                }
+            }
+        }
+
+        self.width = width;
+        self.height = height;
+        self.buf = buf;
+    }
+
+    fn idx(&self, p: Point) -> usize {
+        assert!(self.contains(p), "{:?} out of bounds", p);
+        p.x as usize + p.y as usize * self.width as usize
+    }
+}
+
+impl<T: Clone> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: Point) -> &T {
+        &self.buf[self.idx(p)]
+    }
+}
+
+impl<T: Clone> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, p: Point) -> &mut T {
+        let idx = self.idx(p);
+        &mut self.buf[idx]
+    }
+}
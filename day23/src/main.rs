@@ -1,186 +1,233 @@
-use std::cmp::{max, min, Ordering};
+use space::{BoundingBox, Coord};
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::env;
+use std::error;
 use std::fmt;
 use std::fs;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-struct Coord {
-    x: i32,
-    y: i32,
-    z: i32,
+// Upper limit for the boxes used to describe the best region with --region
+const MAX_REGION_BOXES: usize = 1_000_000;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+#[derive(Debug)]
+enum Error {
+    InvalidInput,
+    NoNanobots,
+    RegionTooLarge(usize),
 }
 
-impl Coord {
-    fn new(x: i32, y: i32, z: i32) -> Self {
-        Coord { x, y, z }
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::RegionTooLarge(max_boxes) => {
+                write!(f, "region does not fit into {} boxes", max_boxes)
+            }
+            _ => write!(f, "{:?}", self),
+        }
     }
+}
 
-    fn origin() -> Self {
-        Coord::new(0, 0, 0)
-    }
+impl error::Error for Error {}
 
-    fn dist(&self, other: &Coord) -> i32 {
-        (self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()
+#[derive(Debug)]
+struct Nanobot {
+    p: Coord<3>,
+    r: i64,
+}
+
+impl Nanobot {
+    fn covers(&self, c: Coord<3>) -> bool {
+        self.p.dist(&c) <= self.r
     }
 }
 
+/// The point closest to the origin that is in range of the most nanobots.
 #[derive(Debug)]
-struct Nanobot {
-    p: Coord,
-    r: i32,
+struct BestPoint {
+    coverage: usize,
+    point: Coord<3>,
 }
 
-// Finds point in interval closest to given value
-fn find_closest_1d(min: i32, max: i32, val: i32) -> i32 {
-    if max >= val && min <= val {
-        val
-    } else if (max - val).abs() < (min - val).abs() {
-        max
-    } else {
-        min
+/// The region in which the most nanobots are in range, as a set of disjoint boxes.
+#[derive(Debug)]
+struct BestRegion {
+    coverage: usize,
+    boxes: Vec<BoundingBox<3>>,
+}
+
+impl BestRegion {
+    fn closest_to_origin(&self) -> Coord<3> {
+        self.boxes
+            .iter()
+            .map(|bb| bb.find_closest(Coord::origin()))
+            .min_by_key(|c| c.dist(&Coord::origin()))
+            .expect("region is empty")
+    }
+
+    fn num_points(&self) -> u64 {
+        self.boxes.iter().map(|bb| bb.num_points()).sum()
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-struct BoundingBox {
-    min: Coord,
-    max: Coord,
+struct NanobotField {
+    bots: Vec<Nanobot>,
 }
 
-impl BoundingBox {
-    fn from_nanobots(bots: &[Nanobot]) -> Self {
-        let mut bb = BoundingBox {
-            min: Coord::new(i32::max_value(), i32::max_value(), i32::max_value()),
-            max: Coord::new(i32::min_value(), i32::min_value(), i32::min_value()),
-        };
+impl NanobotField {
+    fn new(bots: Vec<Nanobot>) -> Self {
+        NanobotField { bots }
+    }
 
-        for bot in bots {
-            bb.min.x = min(bb.min.x, bot.p.x - bot.r);
-            bb.max.x = max(bb.max.x, bot.p.x + bot.r);
-            bb.min.y = min(bb.min.y, bot.p.y - bot.r);
-            bb.max.y = max(bb.max.y, bot.p.y + bot.r);
-            bb.min.z = min(bb.min.z, bot.p.z - bot.r);
-            bb.max.z = max(bb.max.z, bot.p.z + bot.r);
-        }
+    fn coverage(&self, c: Coord<3>) -> usize {
+        self.bots_covering(c).count()
+    }
 
-        assert!(bb.min.x <= bb.max.x);
-        assert!(bb.min.y <= bb.max.y);
-        assert!(bb.min.z <= bb.max.z);
-
-        bb
-    }
-
-    fn split(&self) -> Vec<BoundingBox> {
-        let mut res = Vec::new();
-
-        let border_x = (self.max.x + self.min.x) / 2;
-        let border_y = (self.max.y + self.min.y) / 2;
-        let border_z = (self.max.z + self.min.z) / 2;
-
-        let shift_x = if self.min.x != self.max.x { vec![false, true] } else { vec![false] };
-        let shift_y = if self.min.y != self.max.y { vec![false, true] } else { vec![false] };
-        let shift_z = if self.min.z != self.max.z { vec![false, true] } else { vec![false] };
-
-        for sz in &shift_z {
-            for sy in &shift_y {
-                for sx in &shift_x {
-                    res.push(BoundingBox {
-                        min: Coord::new(
-                            if *sx { border_x + 1 } else { self.min.x },
-                            if *sy { border_y + 1 } else { self.min.y },
-                            if *sz { border_z + 1 } else { self.min.z },
-                        ),
-                        max: Coord::new(
-                            if *sx { self.max.x } else { border_x },
-                            if *sy { self.max.y } else { border_y },
-                            if *sz { self.max.z } else { border_z },
-                        ),
-                    });
-                }
-            }
-        }
+    fn bots_covering(&self, c: Coord<3>) -> impl Iterator<Item = &Nanobot> {
+        self.bots.iter().filter(move |bot| bot.covers(c))
+    }
 
-        res
+    fn strongest(&self) -> Option<&Nanobot> {
+        self.bots.iter().max_by_key(|n| n.r)
     }
 
-    fn find_closest(&self, c: Coord) -> Coord {
-        Coord::new(
-            find_closest_1d(self.min.x, self.max.x, c.x),
-            find_closest_1d(self.min.y, self.max.y, c.y),
-            find_closest_1d(self.min.z, self.max.z, c.z),
-        )
+    fn count_intersecting(&self, bb: &BoundingBox<3>) -> usize {
+        self.bots
+            .iter()
+            .filter(|bot| bb.intersects_ball(bot.p, bot.r))
+            .count()
     }
 
-    fn intersects(&self, bot: &Nanobot) -> bool {
-        self.find_closest(bot.p).dist(&bot.p) <= bot.r
+    // Whether every bot in range of some point of the box is in range of all of it
+    fn is_uniform(&self, bb: &BoundingBox<3>) -> bool {
+        self.bots
+            .iter()
+            .filter(|bot| bb.intersects_ball(bot.p, bot.r))
+            .all(|bot| bb.inside_ball(bot.p, bot.r))
     }
 
-    fn count_intersections(&self, bots: &[Nanobot]) -> usize {
-        bots.iter().filter(|bot| self.intersects(bot)).count()
+    /// Searches boxes by the number of bots they intersect, which is an upper bound for the
+    /// coverage of any point inside, and then by their distance to the origin. Smaller boxes go
+    /// first among equals, so that the search dives towards single points instead of splitting
+    /// every box at the same distance first. Calls `found` for
+    /// each box that lies entirely in the best region, in that order, until it returns false.
+    fn search<F>(&self, mut found: F) -> Result<()>
+    where
+        F: FnMut(usize, BoundingBox<3>) -> Result<bool>,
+    {
+        let bb = BoundingBox::from_balls(self.bots.iter().map(|bot| (bot.p, bot.r)))
+            .ok_or(Error::NoNanobots)?;
+
+        let mut coverage = 0;
+        let mut candidates = BinaryHeap::new();
+        candidates.push((
+            self.bots.len(),
+            Reverse(bb.dist(Coord::origin())),
+            Reverse(bb.max_side()),
+            Candidate(bb),
+        ));
+
+        while let Some((cnt, _, _, Candidate(cand))) = candidates.pop() {
+            if cnt < coverage {
+                break;
+            }
+
+            if self.is_uniform(&cand) {
+                coverage = cnt;
+                if !found(cnt, cand)? {
+                    break;
+                }
+                continue;
+            }
+
+            for child in cand.split() {
+                let cnt = self.count_intersecting(&child);
+                if cnt > 0 && cnt >= coverage {
+                    candidates.push((
+                        cnt,
+                        Reverse(child.dist(Coord::origin())),
+                        Reverse(child.max_side()),
+                        Candidate(child),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
     }
-}
 
-impl Ord for BoundingBox {
-    fn cmp(&self, other: &BoundingBox) -> Ordering {
-        let this_dist = self.find_closest(Coord::origin()).dist(&Coord::origin());
-        let other_dist = other.find_closest(Coord::origin()).dist(&Coord::origin());
-
-        if this_dist < other_dist {
-            Ordering::Greater
-        } else if this_dist > other_dist {
-            Ordering::Less
-        } else {
-            Ordering::Equal
+    /// Finds the point closest to the origin that is in range of the maximum number of
+    /// nanobots. The first box found lies in the best region and is closest to the origin among
+    /// all remaining candidates, so the search stops right there.
+    fn best_point(&self) -> Result<BestPoint> {
+        let mut best = None;
+        self.search(|coverage, bb| {
+            best = Some(BestPoint {
+                coverage,
+                point: bb.find_closest(Coord::origin()),
+            });
+            Ok(false)
+        })?;
+        best.ok_or_else(|| Error::NoNanobots.into())
+    }
+
+    /// Finds all points in range of the maximum number of nanobots, as long as they fit into
+    /// `max_boxes` boxes. The number of boxes grows with the surface of the region, so large
+    /// regions fail with `Error::RegionTooLarge`.
+    fn best_region(&self, max_boxes: usize) -> Result<BestRegion> {
+        let mut best = BestRegion {
+            coverage: 0,
+            boxes: Vec::new(),
+        };
+        self.search(|coverage, bb| {
+            if best.boxes.len() == max_boxes {
+                return Err(Error::RegionTooLarge(max_boxes).into());
+            }
+            best.coverage = coverage;
+            best.boxes.push(bb);
+            Ok(true)
+        })?;
+
+        if best.boxes.is_empty() {
+            return Err(Box::new(Error::NoNanobots));
         }
+
+        Ok(best)
     }
 }
 
-impl PartialOrd for BoundingBox {
-    fn partial_cmp(&self, other: &BoundingBox) -> Option<Ordering> {
-        Some(self.cmp(other))
+// Boxes are only compared as a last resort in the heap, and all orderings are equally fine
+#[derive(Debug)]
+struct Candidate(BoundingBox<3>);
+
+impl PartialEq for Candidate {
+    fn eq(&self, _: &Self) -> bool {
+        true
     }
 }
 
-impl fmt::Debug for BoundingBox {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "BoundingBox: x = {}..{} y = {}..{} z = {}..{}",
-            self.min.x, self.max.x, self.min.y, self.max.y, self.min.z, self.max.z,
-        )
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, _: &Self) -> Ordering {
+        Ordering::Equal
     }
 }
 
-fn part2(nanobots: &[Nanobot]) -> i32 {
-    let bb = BoundingBox::from_nanobots(&nanobots);
-
-    let mut candidates = vec![(bb.count_intersections(&nanobots), bb)];
-    loop {
-        if let Some((_, cand)) = candidates.pop() {
-            if cand.min == cand.max {
-                return cand.min.x.abs() + cand.min.y.abs() + cand.min.z.abs();
-            } else {
-                let children = cand.split();
-                candidates.extend(
-                    children
-                        .into_iter()
-                        .map(|c| (c.count_intersections(&nanobots), c)),
-                );
-            }
-
-            candidates.sort_by_key(|(cnt, bb)| (*cnt, *bb));
-        } else {
-            println!("No candidates left");
-            assert!(false);
-        }
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-fn main() -> Result<(), Box<std::error::Error>> {
+fn main() -> Result<()> {
     let input = fs::read_to_string("input")?;
 
     let mut nanobots = Vec::new();
     for l in input.lines() {
-        let nums: Vec<i32> = l
+        let nums: Vec<i64> = l
             .chars()
             .skip_while(|c| *c != '<')
             .skip(1)
@@ -188,9 +235,11 @@ fn main() -> Result<(), Box<std::error::Error>> {
             .collect::<String>()
             .split(',')
             .map(|s| s.parse())
-            .collect::<Result<_, _>>()?;
-        assert_eq!(nums.len(), 3);
-        let r: i32 = l
+            .collect::<std::result::Result<_, _>>()?;
+        if nums.len() != 3 {
+            return Err(Box::new(Error::InvalidInput));
+        }
+        let r: i64 = l
             .chars()
             .skip_while(|c| *c != 'r')
             .skip(2)
@@ -198,21 +247,38 @@ fn main() -> Result<(), Box<std::error::Error>> {
             .parse()?;
 
         nanobots.push(Nanobot {
-            p: Coord::new(nums[0], nums[1], nums[2]),
+            p: Coord([nums[0], nums[1], nums[2]]),
             r,
         });
     }
 
-    let max_range = nanobots.iter().max_by_key(|n| n.r).unwrap();
+    let field = NanobotField::new(nanobots);
+
+    let max_range = field.strongest().ok_or(Error::NoNanobots)?;
     println!(
         "Part 1: {}",
-        nanobots
-            .iter()
-            .filter(|n| max_range.p.dist(&n.p) <= max_range.r)
-            .count()
+        field.bots.iter().filter(|n| max_range.covers(n.p)).count()
     );
 
-    println!("Part 2: {}", part2(&nanobots));
+    let best = field.best_point()?;
+    println!("Part 2: {}", best.point.dist(&Coord::origin()));
+    println!(
+        "Best point: {:?} in range of {} nanobots",
+        best.point.0, best.coverage
+    );
+    debug_assert_eq!(field.coverage(best.point), best.coverage);
+
+    if env::args().any(|arg| arg == "--region") {
+        match field.best_region(MAX_REGION_BOXES) {
+            Ok(region) => println!(
+                "Best region: {} point(s) in range of {} nanobots, closest to the origin at {:?}",
+                region.num_points(),
+                region.coverage,
+                region.closest_to_origin().0
+            ),
+            Err(err) => println!("Best region: {}", err),
+        }
+    }
 
     Ok(())
 }
@@ -223,15 +289,91 @@ mod tests {
 
     #[test]
     fn example() {
-        let nanobots = vec![
-            Nanobot { p: Coord::new(10, 12, 12), r: 2 },
-            Nanobot { p: Coord::new(12, 14, 12), r: 2 },
-            Nanobot { p: Coord::new(16, 12, 12), r: 4 },
-            Nanobot { p: Coord::new(14, 14, 14), r: 6 },
-            Nanobot { p: Coord::new(50, 50, 50), r: 200 },
-            Nanobot { p: Coord::new(10, 10, 10), r: 5 },
-        ];
-
-        assert_eq!(part2(&nanobots), 36);
+        let field = NanobotField::new(vec![
+            Nanobot {
+                p: Coord([10, 12, 12]),
+                r: 2,
+            },
+            Nanobot {
+                p: Coord([12, 14, 12]),
+                r: 2,
+            },
+            Nanobot {
+                p: Coord([16, 12, 12]),
+                r: 4,
+            },
+            Nanobot {
+                p: Coord([14, 14, 14]),
+                r: 6,
+            },
+            Nanobot {
+                p: Coord([50, 50, 50]),
+                r: 200,
+            },
+            Nanobot {
+                p: Coord([10, 10, 10]),
+                r: 5,
+            },
+        ]);
+
+        let best = field.best_point().unwrap();
+        assert_eq!(best.point.dist(&Coord::origin()), 36);
+        assert_eq!(best.coverage, 5);
+        assert_eq!(field.coverage(best.point), 5);
+
+        let region = field.best_region(10).unwrap();
+        assert_eq!(region.closest_to_origin(), best.point);
+        assert_eq!(region.num_points(), 1);
+        assert_eq!(field.coverage(Coord([12, 12, 12])), 5);
+        assert_eq!(field.bots_covering(Coord([50, 50, 50])).count(), 1);
+    }
+
+    #[test]
+    fn large_region() {
+        let field = NanobotField::new(vec![
+            Nanobot {
+                p: Coord([0, 0, 0]),
+                r: 10,
+            },
+            Nanobot {
+                p: Coord([30, 0, 0]),
+                r: 25,
+            },
+            Nanobot {
+                p: Coord([-100, 0, 0]),
+                r: 5,
+            },
+        ]);
+
+        let best = field.best_point().unwrap();
+        assert_eq!(best.coverage, 2);
+        assert_eq!(best.point, Coord([5, 0, 0]));
+
+        let mut expected = 0;
+        for x in -10..=10 {
+            for y in -10..=10 {
+                for z in -10..=10 {
+                    if field.coverage(Coord([x, y, z])) == 2 {
+                        expected += 1;
+                    }
+                }
+            }
+        }
+        let region = field.best_region(10_000).unwrap();
+        assert_eq!(region.coverage, 2);
+        assert_eq!(region.num_points(), expected);
+        assert_eq!(region.closest_to_origin(), best.point);
+    }
+
+    #[test]
+    fn huge_region() {
+        let inside = NanobotField::new(vec![Nanobot {
+            p: Coord([20_000_000, -30_000_000, 10_000_000]),
+            r: 70_000_000,
+        }]);
+        let best = inside.best_point().unwrap();
+        assert_eq!(best.coverage, 1);
+        assert_eq!(best.point, Coord::origin());
+        assert!(inside.best_region(1000).is_err());
     }
 }
@@ -0,0 +1,97 @@
+use crate::{AtkType, Battle, Faction, Group};
+
+use std::collections::HashMap;
+
+/// xorshift64*, so that generated battles only depend on the seed.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // The state must never be zero, or the generator only ever returns zero
+        match seed ^ 0x9e37_79b9_7f4a_7c15 {
+            0 => Rng(0x9e37_79b9_7f4a_7c15),
+            state => Rng(state),
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // Uniform in `lo..=hi`, up to a negligible modulo bias
+    fn range(&mut self, lo: u32, hi: u32) -> u32 {
+        lo + (self.next() % u64::from(hi - lo + 1)) as u32
+    }
+
+    fn chance(&mut self, one_in: u32) -> bool {
+        self.range(1, one_in) == 1
+    }
+}
+
+/// Generates a random battle with `num_groups` groups per faction, using the given damage types.
+/// Values are in roughly the same ranges as in the puzzle input.
+pub fn generate(seed: u64, num_groups: usize, types: &[AtkType]) -> Battle {
+    assert!(!types.is_empty(), "need at least one damage type");
+
+    let mut rng = Rng::new(seed);
+
+    // Initiatives are unique
+    let mut inits: Vec<u32> = (1..=2 * num_groups as u32).collect();
+    for i in (1..inits.len()).rev() {
+        let j = rng.range(0, i as u32) as usize;
+        inits.swap(i, j);
+    }
+
+    let mut groups = HashMap::new();
+    for (f, faction) in [Faction::ImmuneSystem, Faction::Infection]
+        .iter()
+        .enumerate()
+    {
+        for num in 1..=num_groups {
+            let id = groups.len() + 1;
+            let (mut weak, mut immune) = (Vec::new(), Vec::new());
+            for t in types {
+                if rng.chance(4) {
+                    weak.push(t.clone());
+                } else if rng.chance(6) {
+                    immune.push(t.clone());
+                }
+            }
+
+            let size = rng.range(1, 10_000);
+            let hp = rng.range(1, 15_000);
+            groups.insert(
+                id,
+                Group {
+                    id,
+                    num,
+                    faction: *faction,
+                    size,
+                    hp,
+                    dmg: rng.range(1, (hp / 10).max(1)),
+                    init: inits[f * num_groups + num - 1],
+                    atk_type: types[rng.range(0, types.len() as u32 - 1) as usize].clone(),
+                    weak,
+                    immune,
+                    immune_first: rng.chance(2),
+                },
+            );
+        }
+    }
+
+    Battle::new(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_zero_state() {
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+        assert!((0..10).any(|_| rng.next() != 0));
+    }
+}
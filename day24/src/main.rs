@@ -1,11 +1,20 @@
+mod gen;
+mod whatif;
+
 use regex::Regex;
 
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::env;
 use std::error;
 use std::fmt;
 use std::fs;
+use std::io::{self, Write};
 use std::str;
 
+// Largest boost tried by default in part 2, as every boost means another full battle
+const MAX_BOOST: u32 = 100_000;
+
 #[derive(Debug)]
 enum Error {
     Parse,
@@ -17,11 +26,7 @@ impl fmt::Display for Error {
     }
 }
 
-impl error::Error for Error {
-    fn cause(&self) -> Option<&error::Error> {
-        Some(self)
-    }
-}
+impl error::Error for Error {}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Faction {
@@ -29,33 +34,41 @@ enum Faction {
     Infection,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum AtkType {
-    Slashing,
-    Bludgeoning,
-    Fire,
-    Cold,
-    Radiation,
+impl fmt::Display for Faction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Faction::ImmuneSystem => write!(f, "Immune System"),
+            Faction::Infection => write!(f, "Infection"),
+        }
+    }
 }
 
+/// A damage type. Any lowercase word is accepted, the puzzle input just happens to use five.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct AtkType(String);
+
 impl str::FromStr for AtkType {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "slashing" => AtkType::Slashing,
-            "bludgeoning" => AtkType::Bludgeoning,
-            "fire" => AtkType::Fire,
-            "cold" => AtkType::Cold,
-            "radiation" => AtkType::Radiation,
-            _ => return Err(Error::Parse),
-        })
+        if s.is_empty() || !s.chars().all(|c| c.is_ascii_lowercase()) {
+            return Err(Error::Parse);
+        }
+        Ok(AtkType(s.to_string()))
+    }
+}
+
+impl fmt::Display for AtkType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
 #[derive(Debug, Clone)]
 struct Group {
     id: usize,
+    // Number of the group within its faction, as used in the puzzle's battle narrative
+    num: usize,
     faction: Faction,
     size: u32,
     hp: u32,
@@ -64,82 +77,301 @@ struct Group {
     atk_type: AtkType,
     weak: Vec<AtkType>,
     immune: Vec<AtkType>,
+    // Whether immunities are listed before weaknesses in the puzzle text
+    immune_first: bool,
 }
 
 impl Group {
-    fn calc_damage(&self, other: &Group) -> u32 {
+    // Damage is computed in 64 bits, large boosts easily exceed the range of the attributes
+    fn calc_damage(&self, other: &Group) -> u64 {
         if other.immune.contains(&self.atk_type) {
             0
         } else if other.weak.contains(&self.atk_type) {
-            2 * self.size * self.dmg
+            2 * self.eff_power()
         } else {
-            self.size * self.dmg
+            self.eff_power()
         }
     }
 
-    fn eff_power(&self) -> u32 {
-        self.size * self.dmg
+    fn eff_power(&self) -> u64 {
+        u64::from(self.size) * u64::from(self.dmg)
+    }
+}
+
+fn join(types: &[AtkType]) -> String {
+    types
+        .iter()
+        .map(|t| t.0.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} units each with {} hit points ", self.size, self.hp)?;
+
+        let mut attrs = Vec::new();
+        if !self.weak.is_empty() {
+            attrs.push(format!("weak to {}", join(&self.weak)));
+        }
+        if !self.immune.is_empty() {
+            attrs.push(format!("immune to {}", join(&self.immune)));
+        }
+        if self.immune_first {
+            attrs.reverse();
+        }
+        if !attrs.is_empty() {
+            write!(f, "({}) ", attrs.join("; "))?;
+        }
+
+        write!(
+            f,
+            "with an attack that does {} {} damage at initiative {}",
+            self.dmg, self.atk_type, self.init
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Event {
+    /// An enemy group the attacker could deal damage to during target selection, and whether
+    /// it picked that one
+    Target {
+        attacker: usize,
+        defender: usize,
+        damage: u64,
+        chosen: bool,
+    },
+    Attack {
+        attacker: usize,
+        defender: usize,
+        damage: u64,
+        killed: u32,
+        remaining: u32,
+    },
+}
+
+impl Event {
+    fn to_json(&self, round: usize) -> String {
+        match self {
+            Event::Target {
+                attacker,
+                defender,
+                damage,
+                chosen,
+            } => format!(
+                "{{\"round\":{},\"event\":\"target\",\"attacker\":{},\"defender\":{},\
+                 \"damage\":{},\"chosen\":{}}}",
+                round, attacker, defender, damage, chosen
+            ),
+            Event::Attack {
+                attacker,
+                defender,
+                damage,
+                killed,
+                remaining,
+            } => format!(
+                "{{\"round\":{},\"event\":\"attack\",\"attacker\":{},\"defender\":{},\
+                 \"damage\":{},\"killed\":{},\"remaining\":{}}}",
+                round, attacker, defender, damage, killed, remaining
+            ),
+        }
+    }
+}
+
+/// The state of all groups at the start of a round, and what happened during it.
+#[derive(Debug, Clone)]
+struct Round {
+    number: usize,
+    groups: Vec<Group>,
+    events: Vec<Event>,
+}
+
+impl Round {
+    fn group(&self, id: usize) -> &Group {
+        self.groups
+            .iter()
+            .find(|g| g.id == id)
+            .expect("event refers to unknown group")
+    }
+}
+
+// Formats the round like the example battle in the puzzle text
+impl fmt::Display for Round {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for faction in &[Faction::ImmuneSystem, Faction::Infection] {
+            writeln!(f, "{}:", faction)?;
+            let mut groups: Vec<_> = self
+                .groups
+                .iter()
+                .filter(|g| g.faction == *faction)
+                .collect();
+            groups.sort_by_key(|g| g.num);
+            if groups.is_empty() {
+                writeln!(f, "No groups remain.")?;
+            }
+            for g in groups {
+                writeln!(f, "Group {} contains {} units", g.num, g.size)?;
+            }
+        }
+
+        // Targets are recorded in selection order, but listed by faction and group like in the
+        // puzzle text
+        writeln!(f)?;
+        let mut targets: Vec<(&Group, &Group, u64)> = self
+            .events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Target {
+                    attacker,
+                    defender,
+                    damage,
+                    ..
+                } => Some((self.group(*attacker), self.group(*defender), *damage)),
+                Event::Attack { .. } => None,
+            })
+            .collect();
+        targets.sort_by_key(|(a, d, _)| (a.faction == Faction::ImmuneSystem, a.num, d.num));
+        for (a, d, damage) in targets {
+            writeln!(
+                f,
+                "{} group {} would deal defending group {} {} damage",
+                a.faction, a.num, d.num, damage
+            )?;
+        }
+
+        writeln!(f)?;
+        for event in &self.events {
+            if let Event::Attack {
+                attacker,
+                defender,
+                killed,
+                ..
+            } = event
+            {
+                let (a, d) = (self.group(*attacker), self.group(*defender));
+                writeln!(
+                    f,
+                    "{} group {} attacks defending group {}, killing {} units",
+                    a.faction, a.num, d.num, killed
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Winner(Faction, u64),
+    Stalemate(usize),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Winner(faction, units) => {
+                write!(f, "{} wins with {} units left", faction, units)
+            }
+            Outcome::Stalemate(round) => write!(f, "stalemate in round {}", round),
+        }
     }
 }
 
 #[derive(Debug, Clone)]
 struct Battle {
     groups: HashMap<usize, Group>,
+    round: usize,
 }
 
 impl Battle {
-    fn play_turn(&mut self) {
+    fn new(groups: HashMap<usize, Group>) -> Self {
+        Battle { groups, round: 0 }
+    }
+
+    /// Plays a round of targeting and attacking, and reports what happened.
+    fn play_turn(&mut self) -> Round {
+        self.round += 1;
+        let mut round = Round {
+            number: self.round,
+            groups: self.groups.values().cloned().collect(),
+            events: Vec::new(),
+        };
+
         let mut targets: HashMap<usize, usize> = HashMap::new();
         let mut attacker_ids: Vec<usize> = self.groups.keys().cloned().collect();
         {
-            // Targeting phase
+            // Targeting phase. Initiatives are unique in the puzzle, but modified or generated
+            // battles can repeat them, so remaining ties go to the group that was listed first.
             attacker_ids.sort_by_key(|id| {
                 let g = &self.groups[id];
-                (g.eff_power(), g.init)
+                (g.eff_power(), g.init, Reverse(g.id))
             });
 
             for aid in attacker_ids.iter().rev() {
                 let attacker = &self.groups[aid];
 
-                if let Some(defender) = self
+                let mut candidates: Vec<&Group> = self
                     .groups
                     .values()
                     .filter(|g| g.faction != attacker.faction)
                     .filter(|g| !targets.values().any(|id| *id == g.id))
-                    .max_by_key(|g| (attacker.calc_damage(g), g.eff_power(), g.init))
-                {
-                    if attacker.calc_damage(defender) == 0 {
-                        continue;
-                    }
-
-                    targets.insert(attacker.id, defender.id);
+                    .filter(|g| attacker.calc_damage(g) > 0)
+                    .collect();
+                candidates.sort_by_key(|g| g.id);
+
+                let chosen = candidates
+                    .iter()
+                    .max_by_key(|g| {
+                        (
+                            attacker.calc_damage(g),
+                            g.eff_power(),
+                            g.init,
+                            Reverse(g.id),
+                        )
+                    })
+                    .map(|g| g.id);
+                for defender in candidates {
+                    round.events.push(Event::Target {
+                        attacker: attacker.id,
+                        defender: defender.id,
+                        damage: attacker.calc_damage(defender),
+                        chosen: Some(defender.id) == chosen,
+                    });
+                }
+                if let Some(chosen) = chosen {
+                    targets.insert(attacker.id, chosen);
                 }
             }
         }
 
         {
             // Attacking phase
-            attacker_ids.sort_by_key(|id| self.groups[id].init);
+            attacker_ids.sort_by_key(|id| (self.groups[id].init, Reverse(*id)));
             for attacker_id in attacker_ids.iter().rev() {
                 if let Some(target_id) = targets.get(attacker_id) {
-                    let killed = if let (Some(attacker), Some(target)) =
+                    let (damage, killed) = if let (Some(attacker), Some(target)) =
                         (self.groups.get(attacker_id), self.groups.get(target_id))
                     {
                         let dmg = attacker.calc_damage(target);
-                        dmg / target.hp
+                        (dmg, dmg / u64::from(target.hp))
                     } else {
                         continue;
                     };
 
                     let dead = {
                         let target = self.groups.get_mut(target_id).unwrap();
-                        if killed >= target.size {
-                            target.size = 0;
-                            true
-                        } else {
-                            target.size -= killed;
-                            false
-                        }
+                        let killed = killed.min(u64::from(target.size)) as u32;
+                        target.size -= killed;
+                        round.events.push(Event::Attack {
+                            attacker: *attacker_id,
+                            defender: *target_id,
+                            damage,
+                            killed,
+                            remaining: target.size,
+                        });
+                        target.size == 0
                     };
 
                     if dead {
@@ -148,6 +380,8 @@ impl Battle {
                 }
             }
         }
+
+        round
     }
 
     fn winner(&self) -> Option<Faction> {
@@ -165,8 +399,33 @@ impl Battle {
         }
     }
 
-    fn count_units(&self) -> u32 {
-        self.groups.values().map(|g| g.size).sum()
+    /// Fights until one faction is wiped out, or until a round passes in which no units die.
+    /// From then on, the battle would repeat that round forever.
+    fn fight<F: FnMut(&Round)>(&mut self, mut on_round: F) -> Outcome {
+        loop {
+            if let Some(faction) = self.winner() {
+                return Outcome::Winner(faction, self.count_units());
+            }
+
+            let round = self.play_turn();
+            on_round(&round);
+
+            let killed: u64 = round
+                .events
+                .iter()
+                .map(|e| match e {
+                    Event::Attack { killed, .. } => u64::from(*killed),
+                    Event::Target { .. } => 0,
+                })
+                .sum();
+            if killed == 0 {
+                return Outcome::Stalemate(round.number);
+            }
+        }
+    }
+
+    fn count_units(&self) -> u64 {
+        self.groups.values().map(|g| u64::from(g.size)).sum()
     }
 
     fn boost(&mut self, size: u32) {
@@ -175,103 +434,431 @@ impl Battle {
             .values_mut()
             .filter(|g| g.faction == Faction::ImmuneSystem)
         {
-            g.dmg += size;
+            g.dmg = g.dmg.saturating_add(size);
         }
     }
+
+    /// Finds the smallest boost with which the immune system wins, trying boosts up to `limit`.
+    ///
+    /// Outcomes are not necessarily monotone in the boost: a larger boost can change targeting
+    /// and lose, or stall, a battle that a smaller one won. Bisection may therefore miss the
+    /// minimum, so every boost is tried in increasing order. Returns all outcomes seen.
+    fn min_boost(&self, limit: u32) -> (Option<u32>, Vec<(u32, Outcome)>) {
+        let mut tried = Vec::new();
+        for boost in 0..=limit {
+            let mut b = self.clone();
+            b.boost(boost);
+            let outcome = b.fight(|_| ());
+            tried.push((boost, outcome));
+
+            if let Outcome::Winner(Faction::ImmuneSystem, _) = outcome {
+                return (Some(boost), tried);
+            }
+        }
+
+        (None, tried)
+    }
+
+    /// A boost from which on the battle plays out the same, no matter how much more boost there
+    /// is. Only the number of units killed by immune system attacks and the targeting order of
+    /// the immune system depend on the boost:
+    ///
+    /// - With a boost of at least `size * hp` of every infection group, a single unit deals
+    ///   enough damage to wipe out any group it can hurt, so every attack kills all units.
+    /// - Groups choose targets within their faction in order of effective power
+    ///   `size * (dmg + boost)`. For two groups of different size, the larger one comes first as
+    ///   soon as the boost exceeds the effective power of the smaller one without boost. Groups of
+    ///   the same size are ordered by damage either way. Sizes only shrink during the battle, so
+    ///   exceeding the initial effective power of every immune system group suffices.
+    ///
+    /// The damage dealt to a target does not matter otherwise, since every attacker prefers the
+    /// same targets regardless of the amount.
+    fn max_useful_boost(&self) -> u32 {
+        let wipe_out = self
+            .groups
+            .values()
+            .filter(|g| g.faction == Faction::Infection)
+            .map(|g| g.size.saturating_mul(g.hp))
+            .max()
+            .unwrap_or(0);
+        let order = self
+            .groups
+            .values()
+            .filter(|g| g.faction == Faction::ImmuneSystem)
+            .map(|g| g.size.saturating_mul(g.dmg).saturating_add(1))
+            .max()
+            .unwrap_or(0);
+        wipe_out.max(order)
+    }
 }
 
-fn main() -> Result<(), Box<error::Error>> {
-    let input = fs::read_to_string("input")?;
+impl str::FromStr for Battle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let re = Regex::new(
+            r"^(?P<size>\d+) units each with (?P<hp>\d+) hit points (\((?P<attr>.*)\) )?with an attack that does (?P<dmg>\d+) (?P<type>[a-z]+) damage at initiative (?P<init>\d+)$",
+        )
+        .expect("invalid regex");
+        let num = |caps: &regex::Captures, name| -> Result<u32, Error> {
+            caps.name(name)
+                .ok_or(Error::Parse)?
+                .as_str()
+                .parse()
+                .map_err(|_| Error::Parse)
+        };
 
-    let mut id = 1;
-    let mut groups: HashMap<usize, Group> = HashMap::new();
-    {
-        let re = Regex::new(r"^(?P<size>\d+) units each with (?P<hp>\d+) hit points (\((?P<attr>.*)\) )?with an attack that does (?P<dmg>\d+) (?P<type>[a-z]+) damage at initiative (?P<init>\d+)$")?;
-        let mut faction = Faction::ImmuneSystem;
-        for line in input.lines() {
-            if line.starts_with("Immune System") || line == "" {
-                faction = Faction::ImmuneSystem;
+        let mut groups = HashMap::new();
+        let mut faction = None;
+        let mut count = 0;
+        for line in s.lines() {
+            if line.is_empty() {
                 continue;
-            } else if line.starts_with("Infection") {
-                faction = Faction::Infection;
+            } else if line == "Immune System:" || line == "Infection:" {
+                faction = Some(if line == "Infection:" {
+                    Faction::Infection
+                } else {
+                    Faction::ImmuneSystem
+                });
+                count = 0;
                 continue;
             }
+            count += 1;
 
             let caps = re.captures(line).ok_or(Error::Parse)?;
 
-            let (mut weak, mut immune) = (Vec::new(), Vec::new());
+            let (mut weak, mut immune, mut immune_first) = (Vec::new(), Vec::new(), false);
             if let Some(m) = caps.name("attr") {
-                for attr in m.as_str().split(';') {
-                    let words: Vec<_> = attr
-                        .split_whitespace()
-                        .map(|w| w.trim_matches(','))
-                        .collect();
-                    if words.len() < 3 {
-                        return Err(Box::new(Error::Parse));
-                    }
+                for attr in m.as_str().split("; ") {
+                    let (kind, types) = if let Some(types) = attr.strip_prefix("weak to ") {
+                        (&mut weak, types)
+                    } else if let Some(types) = attr.strip_prefix("immune to ") {
+                        immune_first |= weak.is_empty();
+                        (&mut immune, types)
+                    } else {
+                        return Err(Error::Parse);
+                    };
 
-                    for t in &words[2..] {
-                        match words[0] {
-                            "weak" => weak.push(t.parse()?),
-                            "immune" => immune.push(t.parse()?),
-                            _ => return Err(Box::new(Error::Parse)),
-                        }
+                    for t in types.split(", ") {
+                        kind.push(t.parse()?);
                     }
                 }
             }
 
+            let id = groups.len() + 1;
             let group = Group {
                 id,
-                faction,
-                size: caps.name("size").ok_or(Error::Parse)?.as_str().parse()?,
-                hp: caps.name("hp").ok_or(Error::Parse)?.as_str().parse()?,
-                dmg: caps.name("dmg").ok_or(Error::Parse)?.as_str().parse()?,
-                init: caps.name("init").ok_or(Error::Parse)?.as_str().parse()?,
+                num: count,
+                faction: faction.ok_or(Error::Parse)?,
+                size: num(&caps, "size")?,
+                hp: num(&caps, "hp")?,
+                dmg: num(&caps, "dmg")?,
+                init: num(&caps, "init")?,
                 atk_type: caps.name("type").ok_or(Error::Parse)?.as_str().parse()?,
                 weak,
                 immune,
+                immune_first,
             };
             groups.insert(id, group);
-            id += 1;
         }
+
+        Ok(Battle::new(groups))
     }
+}
+
+// Formats the battle like the puzzle input, so that parsing and formatting round-trips
+impl fmt::Display for Battle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut groups: Vec<_> = self.groups.values().collect();
+        groups.sort_by_key(|g| g.id);
+
+        let mut faction = None;
+        for g in groups {
+            if faction != Some(g.faction) {
+                if faction.is_some() {
+                    writeln!(f)?;
+                }
+                writeln!(f, "{}:", g.faction)?;
+                faction = Some(g.faction);
+            }
+            writeln!(f, "{}", g)?;
+        }
 
-    let battle = Battle { groups };
+        Ok(())
+    }
+}
 
-    let mut b = battle.clone();
-    while b.winner().is_none() {
-        b.play_turn();
+fn main() -> Result<(), Box<dyn error::Error>> {
+    let args: Vec<String> = env::args().collect();
+    if let Some(pos) = args.iter().position(|a| a == "--generate") {
+        let seed = args.get(pos + 1).ok_or(Error::Parse)?.parse()?;
+        let num_groups = match args.get(pos + 2) {
+            Some(n) => n.parse()?,
+            None => 10,
+        };
+        let types = ["slashing", "bludgeoning", "fire", "cold", "radiation"]
+            .iter()
+            .map(|t| t.parse())
+            .collect::<Result<Vec<_>, _>>()?;
+        print!("{}", gen::generate(seed, num_groups, &types));
+        return Ok(());
     }
-    println!("Part 1: {}", b.count_units());
 
-    let mut units_left = 0;
-    let (mut boost, mut step) = (5000, 5000);
-    while step > 0 {
-        step /= 2;
+    let input = fs::read_to_string("input")?;
+    let battle: Battle = input.parse()?;
 
-        let mut b = battle.clone();
-        b.boost(boost);
-        let winner = {
-            let mut w = None;
-            let mut turns = 0;
-            while w.is_none() && turns < 10_000 {
-                b.play_turn();
-                w = b.winner();
-                turns += 1;
+    if let Some(pos) = args.iter().position(|a| a == "--whatif") {
+        let range: i64 = args.get(pos + 1).ok_or(Error::Parse)?.parse()?;
+        let mut types: Vec<AtkType> = battle
+            .groups
+            .values()
+            .flat_map(|g| g.weak.iter().chain(&g.immune).chain(Some(&g.atk_type)))
+            .cloned()
+            .collect();
+        types.sort_by(|a, b| a.0.cmp(&b.0));
+        types.dedup();
+
+        let mut mods = Vec::new();
+        for scope in whatif::scopes(&battle) {
+            for attr in whatif::Attribute::iter() {
+                mods.extend(whatif::sweep(scope, attr, -range..=range));
             }
-            w
-        };
+            mods.extend(whatif::toggles(&battle, scope, &types));
+        }
 
-        match winner {
-            Some(Faction::ImmuneSystem) => {
-                units_left = b.count_units();
-                boost -= step;
+        let (baseline, flips) = whatif::flips(&battle, &mods);
+        println!("Without changes: {}", baseline);
+        println!(
+            "{} of {} changes flip the outcome:",
+            flips.len(),
+            mods.len()
+        );
+        for (m, outcome) in flips {
+            println!("  {}: {}", m, outcome);
+        }
+        return Ok(());
+    }
+
+    let mut log = if args.iter().any(|a| a == "--json") {
+        Some(io::BufWriter::new(fs::File::create("battle.jsonl")?))
+    } else {
+        None
+    };
+
+    let mut b = battle.clone();
+    let mut res = Ok(());
+    let outcome = b.fight(|round| {
+        if args.iter().any(|a| a == "--log") {
+            println!("{}", round);
+        }
+        if let Some(log) = &mut log {
+            for event in &round.events {
+                if res.is_ok() {
+                    res = writeln!(log, "{}", event.to_json(round.number));
+                }
             }
-            Some(Faction::Infection) => boost += step,
-            None => boost += step,
         }
+    });
+    res?;
+    match outcome {
+        Outcome::Winner(_, units) => println!("Part 1: {}", units),
+        Outcome::Stalemate(_) => println!("Part 1: {}", outcome),
+    }
+
+    let max_boost = match args.iter().position(|a| a == "--max-boost") {
+        Some(pos) => args.get(pos + 1).ok_or(Error::Parse)?.parse()?,
+        None => MAX_BOOST,
+    };
+    let useful = battle.max_useful_boost();
+    let limit = useful.min(max_boost);
+    let (boost, tried) = battle.min_boost(limit);
+    if args.iter().any(|a| a == "--boosts") {
+        for (boost, outcome) in &tried {
+            println!("Boost {}: {}", boost, outcome);
+        }
+    }
+    match (boost, tried.last()) {
+        (Some(_), Some((_, Outcome::Winner(_, units)))) => println!("Part 2: {}", units),
+        _ if limit < useful => println!("Part 2: no winning boost up to {}", limit),
+        _ => println!("Part 2: immune system cannot win"),
     }
-    println!("Part 2: {}", units_left);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Immune System:
+17 units each with 5390 hit points (weak to radiation, bludgeoning) with an attack that does \
+4507 fire damage at initiative 2
+989 units each with 1274 hit points (immune to fire; weak to bludgeoning, slashing) with an \
+attack that does 25 slashing damage at initiative 3
+
+Infection:
+801 units each with 4706 hit points (weak to radiation) with an attack that does 116 \
+bludgeoning damage at initiative 1
+4485 units each with 2961 hit points (immune to radiation; weak to fire, cold) with an attack \
+that does 12 slashing damage at initiative 4
+";
+
+    #[test]
+    fn example() {
+        let battle: Battle = EXAMPLE.parse().unwrap();
+        assert_eq!(battle.to_string(), EXAMPLE);
+
+        assert_eq!(
+            battle.clone().fight(|_| ()),
+            Outcome::Winner(Faction::Infection, 5216)
+        );
+
+        // The infection groups have 801 * 4706 and 4485 * 2961 hit points in total
+        assert_eq!(battle.max_useful_boost(), 13_280_085);
+        let (boost, _) = battle.min_boost(2000);
+        assert_eq!(boost, Some(1570));
+
+        let mut boosted = battle.clone();
+        boosted.boost(1570);
+        assert_eq!(
+            boosted.fight(|_| ()),
+            Outcome::Winner(Faction::ImmuneSystem, 51)
+        );
+    }
+
+    #[test]
+    fn narrative() {
+        let mut battle: Battle = EXAMPLE.parse().unwrap();
+        let round = battle.play_turn();
+        assert_eq!(
+            round.to_string(),
+            "Immune System:
+Group 1 contains 17 units
+Group 2 contains 989 units
+Infection:
+Group 1 contains 801 units
+Group 2 contains 4485 units
+
+Infection group 1 would deal defending group 1 185832 damage
+Infection group 1 would deal defending group 2 185832 damage
+Infection group 2 would deal defending group 2 107640 damage
+Immune System group 1 would deal defending group 1 76619 damage
+Immune System group 1 would deal defending group 2 153238 damage
+Immune System group 2 would deal defending group 1 24725 damage
+
+Infection group 2 attacks defending group 2, killing 84 units
+Immune System group 2 attacks defending group 1, killing 4 units
+Immune System group 1 attacks defending group 2, killing 51 units
+Infection group 1 attacks defending group 1, killing 17 units
+"
+        );
+
+        let chosen: Vec<(usize, usize)> = round
+            .events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Target {
+                    attacker,
+                    defender,
+                    chosen: true,
+                    ..
+                } => Some((*attacker, *defender)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(chosen, vec![(3, 1), (1, 4), (4, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn repeated_initiative() {
+        // Whichever group attacks first wipes out the other
+        let text = "Immune System:
+10 units each with 10 hit points with an attack that does 100 fire damage at initiative 2
+
+Infection:
+10 units each with 10 hit points with an attack that does 100 fire damage at initiative 2
+";
+
+        // Every parse uses a differently seeded HashMap
+        for _ in 0..20 {
+            let mut battle: Battle = text.parse().unwrap();
+            assert_eq!(
+                battle.fight(|_| ()),
+                Outcome::Winner(Faction::ImmuneSystem, 10)
+            );
+        }
+    }
+
+    #[test]
+    fn huge_boost() {
+        // The immune system can only hurt the weaker infection group and never wins
+        let text = "Immune System:
+5000 units each with 10 hit points with an attack that does 1 fire damage at initiative 1
+
+Infection:
+1 units each with 10 hit points (immune to fire) with an attack that does 100000 cold damage at initiative 3
+1 units each with 10 hit points (weak to fire) with an attack that does 1 cold damage at initiative 2
+";
+        let battle: Battle = text.parse().unwrap();
+
+        let (boost, tried) = battle.min_boost(900_000);
+        assert_eq!(boost, None);
+        assert_eq!(tried.len(), 900_001);
+        assert_eq!(
+            tried.last().unwrap().1,
+            Outcome::Winner(Faction::Infection, 2)
+        );
+
+        let mut b = battle.clone();
+        b.boost(u32::MAX);
+        assert_eq!(b.fight(|_| ()), Outcome::Winner(Faction::Infection, 2));
+    }
+
+    #[test]
+    fn whatif() {
+        let battle: Battle = EXAMPLE.parse().unwrap();
+
+        let boost = whatif::sweep(
+            whatif::Scope::Faction(Faction::ImmuneSystem),
+            whatif::Attribute::Dmg,
+            vec![1569, 1570],
+        );
+        let (baseline, flips) = whatif::flips(&battle, &boost);
+        assert_eq!(baseline, Outcome::Winner(Faction::Infection, 5216));
+        assert_eq!(flips.len(), 1);
+        assert_eq!(flips[0].0, boost[1]);
+        assert_eq!(flips[0].1, Outcome::Winner(Faction::ImmuneSystem, 51));
+    }
+
+    #[test]
+    fn whatif_large_delta() {
+        let battle: Battle = EXAMPLE.parse().unwrap();
+
+        for &attr in &[whatif::Attribute::Size, whatif::Attribute::Dmg] {
+            let mods = whatif::sweep(
+                whatif::Scope::Faction(Faction::ImmuneSystem),
+                attr,
+                vec![-5_000_000_000, 1_000_000, 5_000_000_000, i64::MAX],
+            );
+            let outcomes = whatif::run(&battle, &mods);
+            assert_eq!(outcomes.len(), 4);
+            for (m, outcome) in &outcomes[1..] {
+                match outcome {
+                    Outcome::Winner(Faction::ImmuneSystem, _) => {}
+                    _ => panic!("{} ended in {}", m, outcome),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generated() {
+        let types: Vec<AtkType> = vec!["acid".parse().unwrap(), "psychic".parse().unwrap()];
+        let battle = gen::generate(42, 5, &types);
+        assert_eq!(battle.groups.len(), 10);
+
+        let text = battle.to_string();
+        assert_eq!(text, gen::generate(42, 5, &types).to_string());
+        assert_eq!(text.parse::<Battle>().unwrap().to_string(), text);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{AtkType, Battle, Faction, Group, Outcome};
+
+use std::fmt;
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    Size,
+    Hp,
+    Dmg,
+    Init,
+}
+
+impl Attribute {
+    pub fn iter() -> impl Iterator<Item = Attribute> {
+        vec![
+            Attribute::Size,
+            Attribute::Hp,
+            Attribute::Dmg,
+            Attribute::Init,
+        ]
+        .into_iter()
+    }
+
+    fn get_mut(self, g: &mut Group) -> &mut u32 {
+        match self {
+            Attribute::Size => &mut g.size,
+            Attribute::Hp => &mut g.hp,
+            Attribute::Dmg => &mut g.dmg,
+            Attribute::Init => &mut g.init,
+        }
+    }
+
+    // Smallest value for which the group still makes sense
+    fn min(self) -> u32 {
+        match self {
+            Attribute::Dmg => 0,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Add(Attribute, i64),
+    AddWeakness(AtkType),
+    RemoveWeakness(AtkType),
+    AddImmunity(AtkType),
+    RemoveImmunity(AtkType),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Group(usize),
+    Faction(Faction),
+}
+
+/// A single change to the groups of a battle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Modification {
+    pub scope: Scope,
+    pub change: Change,
+}
+
+impl Modification {
+    pub fn apply(&self, battle: &Battle) -> Battle {
+        let mut battle = battle.clone();
+        for g in battle.groups.values_mut().filter(|g| match self.scope {
+            Scope::Group(id) => g.id == id,
+            Scope::Faction(f) => g.faction == f,
+        }) {
+            match &self.change {
+                Change::Add(attr, delta) => {
+                    let v = attr.get_mut(g);
+                    *v = i64::from(*v)
+                        .saturating_add(*delta)
+                        .max(i64::from(attr.min()))
+                        .min(i64::from(u32::MAX)) as u32;
+                }
+                Change::AddWeakness(t) => {
+                    g.immune.retain(|i| i != t);
+                    if !g.weak.contains(t) {
+                        g.weak.push(t.clone());
+                    }
+                }
+                Change::RemoveWeakness(t) => g.weak.retain(|w| w != t),
+                Change::AddImmunity(t) => {
+                    g.weak.retain(|w| w != t);
+                    if !g.immune.contains(t) {
+                        g.immune.push(t.clone());
+                    }
+                }
+                Change::RemoveImmunity(t) => g.immune.retain(|i| i != t),
+            }
+        }
+        battle
+    }
+}
+
+impl fmt::Display for Modification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.scope {
+            Scope::Group(id) => write!(f, "group {}: ", id)?,
+            Scope::Faction(faction) => write!(f, "{}: ", faction)?,
+        }
+        match &self.change {
+            Change::Add(attr, delta) => write!(f, "{:?} {:+}", attr, delta),
+            Change::AddWeakness(t) => write!(f, "weak to {}", t),
+            Change::RemoveWeakness(t) => write!(f, "no longer weak to {}", t),
+            Change::AddImmunity(t) => write!(f, "immune to {}", t),
+            Change::RemoveImmunity(t) => write!(f, "no longer immune to {}", t),
+        }
+    }
+}
+
+/// Every scope, i.e. each group on its own and each faction as a whole.
+pub fn scopes(battle: &Battle) -> Vec<Scope> {
+    let mut ids: Vec<_> = battle.groups.keys().cloned().collect();
+    ids.sort();
+
+    ids.into_iter()
+        .map(Scope::Group)
+        .chain(vec![
+            Scope::Faction(Faction::ImmuneSystem),
+            Scope::Faction(Faction::Infection),
+        ])
+        .collect()
+}
+
+/// Changes of `attr` by every delta in `deltas`, except zero.
+pub fn sweep<I>(scope: Scope, attr: Attribute, deltas: I) -> Vec<Modification>
+where
+    I: IntoIterator<Item = i64>,
+{
+    deltas
+        .into_iter()
+        .filter(|d| *d != 0)
+        .map(|d| Modification {
+            scope,
+            change: Change::Add(attr, d),
+        })
+        .collect()
+}
+
+/// Toggles of every given damage type between weakness, immunity and neither.
+pub fn toggles(battle: &Battle, scope: Scope, types: &[AtkType]) -> Vec<Modification> {
+    let mut mods = Vec::new();
+    let affected = battle.groups.values().filter(|g| match scope {
+        Scope::Group(id) => g.id == id,
+        Scope::Faction(f) => g.faction == f,
+    });
+    let (any_weak, any_immune) = affected.fold((Vec::new(), Vec::new()), |(mut w, mut i), g| {
+        w.extend(g.weak.iter().cloned());
+        i.extend(g.immune.iter().cloned());
+        (w, i)
+    });
+
+    for t in types {
+        let change = |c| Modification { scope, change: c };
+        if any_weak.contains(t) {
+            mods.push(change(Change::RemoveWeakness(t.clone())));
+        } else {
+            mods.push(change(Change::AddWeakness(t.clone())));
+        }
+        if any_immune.contains(t) {
+            mods.push(change(Change::RemoveImmunity(t.clone())));
+        } else {
+            mods.push(change(Change::AddImmunity(t.clone())));
+        }
+    }
+    mods
+}
+
+/// Fights the battle once for every modification, spread over all available cores.
+pub fn run(battle: &Battle, mods: &[Modification]) -> Vec<(Modification, Outcome)> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = mods.len().div_ceil(threads).max(1);
+
+    thread::scope(|s| {
+        let handles: Vec<_> = mods
+            .chunks(chunk)
+            .map(|mods| {
+                s.spawn(move || {
+                    mods.iter()
+                        .map(|m| (m.clone(), m.apply(battle).fight(|_| ())))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("battle thread panicked"))
+            .collect()
+    })
+}
+
+fn winner(outcome: &Outcome) -> Option<Faction> {
+    match outcome {
+        Outcome::Winner(faction, _) => Some(*faction),
+        Outcome::Stalemate(_) => None,
+    }
+}
+
+/// Runs all modifications and keeps those after which the battle ends differently, i.e. with
+/// another winner or a stalemate.
+pub fn flips(battle: &Battle, mods: &[Modification]) -> (Outcome, Vec<(Modification, Outcome)>) {
+    let baseline = battle.clone().fight(|_| ());
+    let mut res = run(battle, mods);
+    res.retain(|(_, o)| winner(o) != winner(&baseline));
+    (baseline, res)
+}
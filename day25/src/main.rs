@@ -1,72 +1,219 @@
-use std::cmp;
+mod report;
+
+use space::Coord;
+
 use std::collections::HashMap;
+use std::env;
 use std::error;
 use std::fs;
 
-type Point = (i32, i32, i32, i32);
-
-fn dist(lhs: &Point, rhs: &Point) -> i32 {
-    (lhs.0 - rhs.0).abs() + (lhs.1 - rhs.1).abs() + (lhs.2 - rhs.2).abs() + (lhs.3 - rhs.3).abs()
+/// Disjoint-set forest with union by size and path compression.
+struct DisjointSet {
+    parent: Vec<usize>,
+    size: Vec<usize>,
 }
 
-fn main() -> Result<(), Box<error::Error>> {
-    let input = fs::read_to_string("input")?;
+impl DisjointSet {
+    fn new(n: usize) -> Self {
+        DisjointSet {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+        }
+    }
 
-    let mut clusters = HashMap::new();
-    for (idx, line) in input.lines().enumerate() {
-        let nums: Vec<i32> = line
-            .split(',')
-            .map(|s| s.parse())
-            .collect::<Result<_, _>>()?;
-        assert_eq!(nums.len(), 4);
-        clusters.insert(idx, vec![(nums[0], nums[1], nums[2], nums[3])]);
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
     }
 
-    let mut distances = HashMap::new();
-    for from in 0..clusters.len() {
-        for to in from + 1..clusters.len() {
-            distances.insert((from, to), dist(&clusters[&from][0], &clusters[&to][0]));
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
         }
+
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
     }
+}
 
-    loop {
-        let mut new_distances = distances.clone();
+#[derive(Debug)]
+struct Constellation<const N: usize> {
+    members: Vec<Coord<N>>,
+}
 
-        if let Some(((from, to), _)) = distances.iter().find(|(_, &d)| d <= 3) {
-            for c in clusters.keys() {
-                if c == from || c == to {
-                    continue;
-                }
+impl<const N: usize> Constellation<N> {
+    fn len(&self) -> usize {
+        self.members.len()
+    }
+}
 
-                let cand = if c < to {
-                    *new_distances.get(&(*c, *to)).expect("edge not found")
-                } else {
-                    *new_distances.get(&(*to, *c)).expect("edge not found")
-                };
+/// Groups points into constellations, linking any two points at most `link` apart.
+///
+/// Points are bucketed into a grid of cells `link` wide, so that linked points are always in the
+/// same or adjacent cells and only those need to be compared.
+fn constellations<const N: usize>(points: &[Coord<N>], link: i64) -> Vec<Constellation<N>> {
+    let cell_size = link.max(1);
+    let cell = |p: &Coord<N>| {
+        let mut c = [0; N];
+        for (dim, v) in c.iter_mut().enumerate() {
+            *v = p[dim].div_euclid(cell_size);
+        }
+        c
+    };
 
-                let to_update = if c < from {
-                    new_distances.get_mut(&(*c, *from)).expect("edge not found")
-                } else {
-                    new_distances.get_mut(&(*from, *c)).expect("edge not found")
-                };
+    let mut grid: HashMap<[i64; N], Vec<usize>> = HashMap::new();
+    for (idx, p) in points.iter().enumerate() {
+        grid.entry(cell(p)).or_default().push(idx);
+    }
 
-                *to_update = cmp::min(*to_update, cand);
+    let mut sets = DisjointSet::new(points.len());
+    for (idx, p) in points.iter().enumerate() {
+        let home = cell(p);
+        for offset in 0..3usize.pow(N as u32) {
+            let mut nb = home;
+            let mut rem = offset;
+            for v in nb.iter_mut() {
+                *v += (rem % 3) as i64 - 1;
+                rem /= 3;
             }
 
-            let ps = clusters.remove(to).expect("cluster not found");
-            clusters
-                .get_mut(from)
-                .expect("cluster not found")
-                .extend(ps);
-            new_distances.retain(|(f, t), _| f != to && t != to);
-        } else {
-            break;
+            for &other in grid.get(&nb).into_iter().flatten() {
+                if other > idx && p.dist(&points[other]) <= link {
+                    sets.union(idx, other);
+                }
+            }
         }
+    }
 
-        distances = new_distances
+    let mut index: HashMap<usize, usize> = HashMap::new();
+    let mut clusters: Vec<Constellation<N>> = Vec::new();
+    for (idx, p) in points.iter().enumerate() {
+        let root = sets.find(idx);
+        let c = *index.entry(root).or_insert_with(|| {
+            clusters.push(Constellation {
+                members: Vec::new(),
+            });
+            clusters.len() - 1
+        });
+        clusters[c].members.push(*p);
     }
 
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.len()));
+    clusters
+}
+
+fn parse<const N: usize>(input: &str) -> Result<Vec<Coord<N>>, Box<dyn error::Error>> {
+    let mut points = Vec::new();
+    for line in input.lines() {
+        let nums: Vec<i64> = line
+            .split(',')
+            .map(|s| s.trim().parse())
+            .collect::<Result<_, _>>()?;
+        let mut p = Coord([0; N]);
+        if nums.len() != N {
+            return Err(format!("expected {} coordinates: {}", N, line).into());
+        }
+        p.0.copy_from_slice(&nums);
+        points.push(p);
+    }
+    Ok(points)
+}
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    let input = fs::read_to_string("input")?;
+    let points: Vec<Coord<4>> = parse(&input)?;
+
+    let clusters = constellations(&points, 3);
     println!("Part 1: {} clusters", clusters.len());
 
+    let args: Vec<String> = env::args().collect();
+    if args.iter().any(|a| a == "--report") {
+        report::print_report(&clusters);
+    }
+    if args.iter().any(|a| a == "--csv") {
+        report::write_csv(&mut fs::File::create("constellations.csv")?, &clusters)?;
+    }
+    if args.iter().any(|a| a == "--json") {
+        report::write_json(&mut fs::File::create("constellations.json")?, &clusters)?;
+    }
+    if let Some(pos) = args.iter().position(|a| a == "--project") {
+        let axes: Vec<usize> = args
+            .get(pos + 1)
+            .ok_or("--project expects two axes, e.g. 0,1")?
+            .split(',')
+            .map(|s| s.parse())
+            .collect::<Result<_, _>>()?;
+        if axes.len() != 2 || axes.iter().any(|&a| a >= 4) {
+            return Err("--project expects two axes between 0 and 3".into());
+        }
+        report::write_projection("projection.ppm", &clusters, (axes[0], axes[1]))?;
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(input: &str, expected: usize) {
+        let points: Vec<Coord<4>> = parse(input).unwrap();
+        assert_eq!(constellations(&points, 3).len(), expected);
+    }
+
+    #[test]
+    fn examples() {
+        check(
+            "0,0,0,0\n3,0,0,0\n0,3,0,0\n0,0,3,0\n0,0,0,3\n0,0,0,6\n9,0,0,0\n12,0,0,0",
+            2,
+        );
+        check(
+            "-1,2,2,0\n0,0,2,-2\n0,0,0,-2\n-1,2,0,0\n-2,-2,-2,2\n3,0,2,-1\n-1,3,2,2\n\
+             -1,0,-1,0\n0,2,1,-2\n3,0,0,0",
+            4,
+        );
+        check(
+            "1,-1,0,1\n2,0,-1,0\n3,2,-1,0\n0,0,3,1\n0,0,-1,-1\n2,3,-2,0\n-2,2,0,0\n\
+             2,-2,0,-1\n1,-1,0,-1\n3,2,0,2",
+            3,
+        );
+        check(
+            "1,-1,-1,-2\n-2,-2,0,1\n0,2,1,3\n-2,3,-2,1\n0,2,3,-2\n-1,-1,1,-2\n0,-2,-1,0\n\
+             -2,2,3,-1\n1,2,2,0\n-1,-2,0,-2",
+            8,
+        );
+    }
+
+    #[test]
+    fn stats() {
+        let c = Constellation {
+            members: vec![
+                Coord([0, 0, 0, 0]),
+                Coord([3, 0, 0, 0]),
+                Coord([3, 0, -3, 0]),
+            ],
+        };
+        assert_eq!(c.diameter(), 6);
+        assert_eq!(c.centroid(), [2., 0., -1., 0.]);
+        assert_eq!(c.bounding_box().min, Coord([0, 0, -3, 0]));
+        assert_eq!(c.bounding_box().max, Coord([3, 0, 0, 0]));
+    }
+
+    #[test]
+    fn members() {
+        let points = vec![Coord([0, 0]), Coord([5, 0]), Coord([0, 1]), Coord([5, 2])];
+        let clusters = constellations(&points, 2);
+        assert_eq!(clusters.len(), 2);
+        assert!(clusters.iter().all(|c| c.len() == 2));
+        assert!(clusters
+            .iter()
+            .any(|c| c.members.contains(&Coord([0, 0])) && c.members.contains(&Coord([0, 1]))));
+    }
+}
@@ -0,0 +1,183 @@
+use crate::Constellation;
+use space::{BoundingBox, Coord};
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+impl<const N: usize> Constellation<N> {
+    pub fn bounding_box(&self) -> BoundingBox<N> {
+        BoundingBox::from_balls(self.members.iter().map(|p| (*p, 0)))
+            .expect("constellation without members")
+    }
+
+    /// Largest distance between two members.
+    ///
+    /// The Manhattan distance of two points is the largest difference of their projections onto
+    /// any of the vectors with all components ±1, so it suffices to check the extremes along
+    /// each of these.
+    pub fn diameter(&self) -> i64 {
+        (0..1usize << N)
+            .map(|signs| {
+                let proj = |p: &Coord<N>| {
+                    (0..N)
+                        .map(|dim| {
+                            if signs & (1 << dim) != 0 {
+                                -p[dim]
+                            } else {
+                                p[dim]
+                            }
+                        })
+                        .sum::<i64>()
+                };
+                let min = self.members.iter().map(proj).min().unwrap_or(0);
+                let max = self.members.iter().map(proj).max().unwrap_or(0);
+                max - min
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn centroid(&self) -> [f64; N] {
+        let mut c = [0.; N];
+        for p in &self.members {
+            for (dim, v) in c.iter_mut().enumerate() {
+                *v += p[dim] as f64;
+            }
+        }
+        for v in &mut c {
+            *v /= self.len() as f64;
+        }
+        c
+    }
+}
+
+fn join<T: ToString>(vals: &[T], sep: &str) -> String {
+    vals.iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+pub fn print_report<const N: usize>(clusters: &[Constellation<N>]) {
+    for (id, c) in clusters.iter().enumerate() {
+        let bb = c.bounding_box();
+        println!("Constellation {}: {} point(s)", id, c.len());
+        println!(
+            "  bounding box: ({}) - ({})",
+            join(&bb.min.0, ", "),
+            join(&bb.max.0, ", ")
+        );
+        println!("  diameter: {}", c.diameter());
+        println!(
+            "  centroid: ({})",
+            join(
+                &c.centroid()
+                    .iter()
+                    .map(|v| format!("{:.2}", v))
+                    .collect::<Vec<_>>(),
+                ", "
+            )
+        );
+        for p in &c.members {
+            println!("    {}", join(&p.0, ","));
+        }
+    }
+}
+
+/// Writes one row per point, tagged with the id of its constellation.
+pub fn write_csv<W: Write, const N: usize>(
+    out: &mut W,
+    clusters: &[Constellation<N>],
+) -> io::Result<()> {
+    let axes: Vec<_> = (0..N).map(|dim| format!("x{}", dim)).collect();
+    writeln!(out, "constellation,{}", axes.join(","))?;
+    for (id, c) in clusters.iter().enumerate() {
+        for p in &c.members {
+            writeln!(out, "{},{}", id, join(&p.0, ","))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_json<W: Write, const N: usize>(
+    out: &mut W,
+    clusters: &[Constellation<N>],
+) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (id, c) in clusters.iter().enumerate() {
+        let bb = c.bounding_box();
+        let members: Vec<_> = c
+            .members
+            .iter()
+            .map(|p| format!("[{}]", join(&p.0, ",")))
+            .collect();
+        write!(
+            out,
+            "  {{\"id\":{},\"size\":{},\"diameter\":{},\"min\":[{}],\"max\":[{}],\
+             \"centroid\":[{}],\"members\":[{}]}}",
+            id,
+            c.len(),
+            c.diameter(),
+            join(&bb.min.0, ","),
+            join(&bb.max.0, ","),
+            join(&c.centroid(), ","),
+            members.join(","),
+        )?;
+        writeln!(out, "{}", if id + 1 < clusters.len() { "," } else { "" })?;
+    }
+    writeln!(out, "]")
+}
+
+// Spreads hues evenly using the golden ratio, so neighboring ids get distinct colors
+fn color(id: usize) -> [u8; 3] {
+    let h = (id as f64 * 0.618_033_988_75).fract() * 6.;
+    let x = (1. - (h % 2. - 1.).abs()) * 255.;
+    let (r, g, b) = match h as u32 {
+        0 => (255., x, 0.),
+        1 => (x, 255., 0.),
+        2 => (0., 255., x),
+        3 => (0., x, 255.),
+        4 => (x, 0., 255.),
+        _ => (255., 0., x),
+    };
+    [r as u8, g as u8, b as u8]
+}
+
+/// Renders all points projected onto the plane spanned by axes `a` and `b`, colored by
+/// constellation, as a PPM image.
+pub fn write_projection<P: AsRef<Path>, const N: usize>(
+    path: P,
+    clusters: &[Constellation<N>],
+    (a, b): (usize, usize),
+) -> io::Result<()> {
+    const SIZE: i64 = 512;
+    const DOT: i64 = 2;
+
+    let bb = BoundingBox::from_balls(clusters.iter().flat_map(|c| &c.members).map(|p| (*p, 0)));
+    let bb = match bb {
+        Some(bb) => bb,
+        None => return Ok(()),
+    };
+    let range = (bb.max[a] - bb.min[a]).max(bb.max[b] - bb.min[b]).max(1);
+    let to_px = |v: i64, min: i64| DOT + (v - min) * (SIZE - 1 - 2 * DOT) / range;
+
+    let mut pixels = vec![[255u8; 3]; (SIZE * SIZE) as usize];
+    for (id, c) in clusters.iter().enumerate() {
+        for p in &c.members {
+            let (x, y) = (to_px(p[a], bb.min[a]), to_px(p[b], bb.min[b]));
+            for py in y - DOT..=y + DOT {
+                for px in x - DOT..=x + DOT {
+                    pixels[(px + py * SIZE) as usize] = color(id);
+                }
+            }
+        }
+    }
+
+    let mut out = io::BufWriter::new(fs::File::create(path)?);
+    write!(out, "P6\n{} {}\n255\n", SIZE, SIZE)?;
+    for px in &pixels {
+        out.write_all(px)?;
+    }
+    out.flush()
+}
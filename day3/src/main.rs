@@ -1,76 +1,125 @@
 extern crate regex;
 
+mod render;
+mod sweep;
+
 use regex::Regex;
+use std::env;
+use std::error;
+use std::fmt;
 use std::fs;
-use std::num::ParseIntError;
-use std::str;
-use std::str::FromStr;
+use std::sync::OnceLock;
 
-struct Claim {
-    id: usize,
-    pos: (usize, usize),
-    size: (usize, usize),
+#[derive(Debug)]
+pub enum Error {
+    InvalidClaim { line: usize, text: String },
 }
 
-impl FromStr for Claim {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(r"#(\d+) @ (\d+),(\d+): (\d+)x(\d+)").unwrap();
-        let caps = match re.captures(s) {
-            Some(c) => c,
-            None => panic!("Error matching: {}", s),
-        };
-
-        Ok(Claim {
-            id: caps.get(1).unwrap().as_str().parse()?,
-            pos: (
-                caps.get(2).unwrap().as_str().parse()?,
-                caps.get(3).unwrap().as_str().parse()?,
-            ),
-            size: (
-                caps.get(4).unwrap().as_str().parse()?,
-                caps.get(5).unwrap().as_str().parse()?,
-            ),
-        })
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidClaim { line, text } => {
+                write!(f, "invalid claim in line {}: {:?}", line, text)
+            }
+        }
     }
 }
 
-const SHEET_SIZE: usize = 1000;
+impl error::Error for Error {}
+
+pub struct Claim {
+    id: usize,
+    pos: (i64, i64),
+    size: (i64, i64),
+}
+
+fn claim_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^#(\d+) @ (-?\d+),(-?\d+): (\d+)x(\d+)$").unwrap())
+}
 
-fn main() -> Result<(), Box<std::error::Error>> {
+/// Parses the claim in `text`, which is line `line_no` of the input.
+fn parse_claim(line_no: usize, text: &str) -> Result<Claim, Error> {
+    let invalid = || Error::InvalidClaim {
+        line: line_no,
+        text: text.to_string(),
+    };
+    let caps = claim_regex().captures(text).ok_or_else(invalid)?;
+    let num = |idx: usize| caps[idx].parse().map_err(|_| invalid());
+
+    Ok(Claim {
+        id: num(1)? as usize,
+        pos: (num(2)?, num(3)?),
+        size: (num(4)?, num(5)?),
+    })
+}
+
+fn parse_claims(input: &str) -> Result<Vec<Claim>, Error> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| parse_claim(idx + 1, line))
+        .collect()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let input = fs::read_to_string("input")?;
-    let claims: Vec<Claim> = input.lines().map(|s| s.parse()).collect::<Result<_, _>>()?;
+    let claims = parse_claims(&input)?;
 
-    let mut frequencies = vec![0usize; SHEET_SIZE * SHEET_SIZE];
-    for c in &claims {
-        for x in c.pos.0..c.pos.0 + c.size.0 {
-            for y in c.pos.1..c.pos.1 + c.size.1 {
-                frequencies[x + y * SHEET_SIZE] += 1;
-            }
-        }
+    let overlaps = sweep::sweep(&claims);
+    println!("Square inches: {}", overlaps.area);
+
+    let intact = overlaps.intact();
+    match intact.first() {
+        Some(id) => println!("ID of non-overlapping claim: {}", id),
+        None => panic!("No non-overlapping claim found"),
+    }
+
+    let args: Vec<String> = env::args().collect();
+    if args.iter().any(|a| a == "--render") {
+        render::write_heatmap("fabric.ppm", &claims, &intact)?;
     }
+    if let Some(pos) = args.iter().position(|a| a == "--overlaps") {
+        let id: usize = args
+            .get(pos + 1)
+            .ok_or("--overlaps expects a claim id")?
+            .parse()?;
+        println!("Claims overlapping #{}: {:?}", id, overlaps.overlapping(id));
+    }
+
+    Ok(())
+}
 
-    println!(
-        "Square inches: {}",
-        frequencies.iter().filter(|&&n| n > 1).count()
-    );
-
-    let intact = match claims.iter().find(|&c| {
-        for x in c.pos.0..c.pos.0 + c.size.0 {
-            for y in c.pos.1..c.pos.1 + c.size.1 {
-                if frequencies[x + y * SHEET_SIZE] > 1 {
-                    return false;
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_errors() {
+        match parse_claims("#1 @ 1,3: 4x4\n#2 @ 3,1 4x4\n") {
+            Err(Error::InvalidClaim { line, text }) => {
+                assert_eq!(line, 2);
+                assert_eq!(text, "#2 @ 3,1 4x4");
             }
+            Ok(_) => panic!("parsing should fail"),
         }
-        true
-    }) {
-        Some(c) => c,
-        None => panic!("No non-overlapping claim found"),
-    };
 
-    println!("ID of non-overlapping claim: {}", intact.id);
+        match parse_claim(7, "#1 @ 1,3: 99999999999999999999x4") {
+            Err(Error::InvalidClaim { line, .. }) => assert_eq!(line, 7),
+            Ok(_) => panic!("parsing should fail"),
+        }
+        assert_eq!(parse_claims("#1 @ 1,3: 4x4\n").unwrap()[0].size, (4, 4));
+    }
+
+    #[test]
+    fn heatmap_with_empty_claim() {
+        let claims = parse_claims("#1 @ 0,0: 4x4\n#2 @ 4,0: 0x4\n").unwrap();
+        let overlaps = sweep::sweep(&claims);
+        let path = std::env::temp_dir().join("day3_empty_claim.ppm");
+        render::write_heatmap(&path, &claims, &overlaps.intact()).unwrap();
 
-    Ok(())
+        let image = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(image.starts_with(b"P6\n4 4\n255\n"));
+    }
 }
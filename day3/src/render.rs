@@ -0,0 +1,89 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use Claim;
+
+// Largest width or height of the image; larger sheets are scaled down
+const MAX_SIZE: i64 = 2000;
+
+const INTACT: [u8; 3] = [0, 200, 0];
+
+// Black for unclaimed fabric, then from blue for a single claim through red to yellow
+fn heat(count: u32, max: u32) -> [u8; 3] {
+    if count == 0 {
+        return [0, 0, 0];
+    }
+    let t = if max > 1 {
+        f64::from(count - 1) / f64::from(max - 1)
+    } else {
+        0.
+    };
+    if t < 0.5 {
+        let t = t * 2.;
+        [(255. * t) as u8, 0, (255. * (1. - t)) as u8]
+    } else {
+        let t = (t - 0.5) * 2.;
+        [255, (255. * t) as u8, 0]
+    }
+}
+
+/// Writes a PPM image of the fabric, colored by the number of claims covering each square inch,
+/// with the claims in `intact` drawn in green.
+pub fn write_heatmap<P: AsRef<Path>>(
+    path: P,
+    claims: &[Claim],
+    intact: &[usize],
+) -> io::Result<()> {
+    // Claims without any area cover no fabric and are not drawn at all
+    let claims: Vec<&Claim> = claims
+        .iter()
+        .filter(|c| c.size.0 > 0 && c.size.1 > 0)
+        .collect();
+
+    let (mut min, mut max) = ((i64::MAX, i64::MAX), (i64::MIN, i64::MIN));
+    for c in &claims {
+        min = (min.0.min(c.pos.0), min.1.min(c.pos.1));
+        max = (max.0.max(c.pos.0 + c.size.0), max.1.max(c.pos.1 + c.size.1));
+    }
+    if min.0 > max.0 {
+        min = (0, 0);
+        max = (1, 1);
+    }
+
+    let scale = ((max.0 - min.0).max(max.1 - min.1) + MAX_SIZE - 1) / MAX_SIZE;
+    let width = ((max.0 - min.0 + scale - 1) / scale) as usize;
+    let height = ((max.1 - min.1 + scale - 1) / scale) as usize;
+    let to_px = |from: i64, to: i64, origin: i64| {
+        let from = (from - origin) / scale;
+        // Always cover at least one pixel
+        let to = ((to - origin + scale - 1) / scale).max(from + 1);
+        from as usize..to as usize
+    };
+
+    let mut counts = vec![0u32; width * height];
+    for c in &claims {
+        for y in to_px(c.pos.1, c.pos.1 + c.size.1, min.1) {
+            for x in to_px(c.pos.0, c.pos.0 + c.size.0, min.0) {
+                counts[x + y * width] += 1;
+            }
+        }
+    }
+
+    let max_count = counts.iter().cloned().max().unwrap_or(0);
+    let mut pixels: Vec<[u8; 3]> = counts.iter().map(|&n| heat(n, max_count)).collect();
+    for c in claims.iter().filter(|c| intact.contains(&c.id)) {
+        for y in to_px(c.pos.1, c.pos.1 + c.size.1, min.1) {
+            for x in to_px(c.pos.0, c.pos.0 + c.size.0, min.0) {
+                pixels[x + y * width] = INTACT;
+            }
+        }
+    }
+
+    let mut out = io::BufWriter::new(fs::File::create(path)?);
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    for px in &pixels {
+        out.write_all(px)?;
+    }
+    out.flush()
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use Claim;
+
+/// Result of sweeping a vertical line across all claims.
+pub struct Overlaps {
+    /// Area covered by two or more claims
+    pub area: u64,
+    ids: Vec<usize>,
+    neighbors: HashMap<usize, Vec<usize>>,
+}
+
+impl Overlaps {
+    /// Ids of all claims that overlap the claim with the given id.
+    pub fn overlapping(&self, id: usize) -> &[usize] {
+        self.neighbors.get(&id).map_or(&[], |n| &n[..])
+    }
+
+    /// Ids of all claims that do not overlap any other claim.
+    pub fn intact(&self) -> Vec<usize> {
+        self.ids
+            .iter()
+            .filter(|id| self.overlapping(**id).is_empty())
+            .cloned()
+            .collect()
+    }
+}
+
+// Length of the parts of the y axis covered by at least two of the given intervals
+fn double_covered(intervals: &[(i64, i64)]) -> u64 {
+    let mut edges: Vec<(i64, i64)> = intervals
+        .iter()
+        .flat_map(|&(from, to)| vec![(from, 1), (to, -1)])
+        .collect();
+    // Intervals are half-open, so ends go before starts at the same y
+    edges.sort();
+
+    let (mut len, mut depth, mut prev) = (0, 0, 0);
+    for (y, delta) in edges {
+        if depth >= 2 {
+            len += (y - prev) as u64;
+        }
+        depth += delta;
+        prev = y;
+    }
+    len
+}
+
+/// Sweeps across the claims from left to right, keeping the set of claims that intersect the
+/// sweep line. The sheet is only ever looked at where claims start or end, so coordinates can
+/// be arbitrarily large or negative.
+pub fn sweep(claims: &[Claim]) -> Overlaps {
+    let mut events: Vec<(i64, bool, usize)> = Vec::new();
+    for (idx, c) in claims.iter().enumerate() {
+        if c.size.0 > 0 && c.size.1 > 0 {
+            events.push((c.pos.0, true, idx));
+            events.push((c.pos.0 + c.size.0, false, idx));
+        }
+    }
+    // Claims are half-open, so ones ending at x are removed before the ones starting there
+    events.sort();
+
+    let y_range = |c: &Claim| (c.pos.1, c.pos.1 + c.size.1);
+
+    let mut overlaps = Overlaps {
+        area: 0,
+        ids: claims.iter().map(|c| c.id).collect(),
+        neighbors: HashMap::new(),
+    };
+    let mut active: Vec<usize> = Vec::new();
+    let mut prev_x = events.first().map_or(0, |e| e.0);
+    for (x, is_start, idx) in events {
+        if x != prev_x {
+            let intervals: Vec<_> = active.iter().map(|&a| y_range(&claims[a])).collect();
+            overlaps.area += (x - prev_x) as u64 * double_covered(&intervals);
+            prev_x = x;
+        }
+
+        if !is_start {
+            active.retain(|&a| a != idx);
+            continue;
+        }
+
+        let (from, to) = y_range(&claims[idx]);
+        for &other in &active {
+            let (other_from, other_to) = y_range(&claims[other]);
+            if from < other_to && other_from < to {
+                let (id, other_id) = (claims[idx].id, claims[other].id);
+                overlaps.neighbors.entry(id).or_default().push(other_id);
+                overlaps.neighbors.entry(other_id).or_default().push(id);
+            }
+        }
+        active.push(idx);
+    }
+
+    for n in overlaps.neighbors.values_mut() {
+        n.sort();
+    }
+    overlaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parse_claims;
+
+    #[test]
+    fn example() {
+        let claims = parse_claims("#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2").unwrap();
+
+        let overlaps = sweep(&claims);
+        assert_eq!(overlaps.area, 4);
+        assert_eq!(overlaps.intact(), vec![3]);
+        assert_eq!(overlaps.overlapping(1), &[2]);
+    }
+
+    #[test]
+    fn large_and_negative() {
+        let claims = parse_claims(
+            "#1 @ -5000000000,-3: 6000000000x4\n\
+             #2 @ 999999999,0: 2x2\n\
+             #3 @ -10,-10: 5x5",
+        )
+        .unwrap();
+
+        let overlaps = sweep(&claims);
+        assert_eq!(overlaps.area, 1);
+        assert_eq!(overlaps.overlapping(1), &[2]);
+        assert_eq!(overlaps.intact(), vec![3]);
+    }
+}
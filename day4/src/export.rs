@@ -0,0 +1,45 @@
+use std::io::{self, Write};
+
+use strategy;
+use timeline::Timeline;
+
+/// Writes one row per guard, with their total minutes asleep and how often they were asleep
+/// during each minute of the midnight hour.
+pub fn write_csv<W: Write>(out: &mut W, timeline: &Timeline) -> io::Result<()> {
+    let minutes: Vec<_> = (0..60).map(|m| format!("m{:02}", m)).collect();
+    writeln!(out, "guard,total,{}", minutes.join(","))?;
+    for guard in timeline.guards() {
+        let hist: Vec<_> = strategy::midnight_hour(timeline, guard)
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        writeln!(
+            out,
+            "{},{},{}",
+            guard,
+            timeline.total_sleep(guard),
+            hist.join(",")
+        )?;
+    }
+    Ok(())
+}
+
+pub fn write_json<W: Write>(out: &mut W, timeline: &Timeline) -> io::Result<()> {
+    let guards = timeline.guards();
+    writeln!(out, "[")?;
+    for (idx, guard) in guards.iter().enumerate() {
+        let hist: Vec<_> = strategy::midnight_hour(timeline, *guard)
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        writeln!(
+            out,
+            "  {{\"guard\":{},\"total\":{},\"minutes\":[{}]}}{}",
+            guard,
+            timeline.total_sleep(*guard),
+            hist.join(","),
+            if idx + 1 < guards.len() { "," } else { "" }
+        )?;
+    }
+    writeln!(out, "]")
+}
@@ -1,37 +1,44 @@
 extern crate chrono;
 extern crate regex;
 
+mod export;
+mod strategy;
+mod timeline;
+mod validate;
+
 use chrono::NaiveDateTime;
-use chrono::Timelike;
 
 use regex::Regex;
 
-use std::collections::HashMap;
+use std::env;
 use std::error;
 use std::fmt;
 use std::fs;
 use std::str::FromStr;
 
-#[derive(Debug)]
-enum Event {
+#[derive(Debug, Clone)]
+pub enum Event {
     BeginShift { id: u32 },
     WakeUp,
     FallAsleep,
 }
 
-#[derive(Debug)]
-struct Record {
+#[derive(Debug, Clone)]
+pub struct Record {
     datetime: NaiveDateTime,
     event: Event,
+    // Line in the input, or 0 if unknown
+    line: usize,
 }
 
 #[derive(Debug)]
-enum Error {
+pub enum Error {
     Record,
     ParseRecord,
     ParseInt(std::num::ParseIntError),
     ParseRegex(regex::Error),
     ParseDT(chrono::format::ParseError),
+    InvalidLog(Vec<validate::Issue>),
 }
 
 impl From<std::num::ParseIntError> for Error {
@@ -54,15 +61,23 @@ impl From<chrono::format::ParseError> for Error {
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Error::ParseInt(err) => write!(f, "ParseInt: {}", err),
+            Error::ParseRegex(err) => write!(f, "ParseRegex: {}", err),
+            Error::ParseDT(err) => write!(f, "ParseDT: {}", err),
+            Error::InvalidLog(issues) => {
+                write!(f, "InvalidLog:")?;
+                for issue in issues {
+                    write!(f, "\n  {}", issue)?;
+                }
+                Ok(())
+            }
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
 
-impl error::Error for Error {
-    fn cause(&self) -> Option<&error::Error> {
-        Some(self)
-    }
-}
+impl error::Error for Error {}
 
 impl FromStr for Record {
     type Err = Error;
@@ -92,54 +107,234 @@ impl FromStr for Record {
             } else {
                 return Err(Error::ParseRecord);
             },
+            line: 0,
         })
     }
 }
 
-fn main() -> Result<(), Box<std::error::Error>> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let input = fs::read_to_string("input")?;
 
-    let mut records: Vec<Record> = input.lines().map(|l| l.parse()).collect::<Result<_, _>>()?;
-    records.sort_by(|lhs, rhs| lhs.datetime.cmp(&rhs.datetime));
+    let mut records = input
+        .lines()
+        .enumerate()
+        .map(|(idx, l)| {
+            let mut rec: Record = l.parse()?;
+            rec.line = idx + 1;
+            Ok(rec)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    records.sort_by_key(|rec| rec.datetime);
+
+    let args: Vec<String> = env::args().collect();
+    let (repaired, issues) = validate::validate(&records);
+    if !issues.is_empty() {
+        if !args.iter().any(|a| a == "--lenient") {
+            return Err(Box::new(Error::InvalidLog(issues)));
+        }
+        for issue in &issues {
+            eprintln!("Warning: repaired {}", issue);
+        }
+    }
+    let records = repaired;
+
+    let timeline = timeline::Timeline::from_records(&records);
+    let top: usize = match args.iter().position(|a| a == "--top") {
+        Some(pos) => args.get(pos + 1).ok_or(Error::Record)?.parse()?,
+        None => 1,
+    };
 
-    let mut sleepy_times = HashMap::new();
+    for (num, rankings) in [
+        strategy::strategy1(&timeline),
+        strategy::strategy2(&timeline),
+    ]
+    .iter()
+    .enumerate()
     {
-        let (mut on_duty, mut from) = (0, 0);
-        for rec in &records {
-            match rec.event {
-                Event::BeginShift { id } => on_duty = id,
-                Event::FallAsleep => from = rec.datetime.minute(),
-                Event::WakeUp => {
-                    let until = rec.datetime.minute();
-                    for i in from..until {
-                        sleepy_times.entry(on_duty).or_insert(vec![0u32; 60])[i as usize] += 1;
-                    }
-                }
+        let best = rankings.first().ok_or(Error::Record)?;
+        println!("=== Strategy #{}", num + 1);
+        println!("Sleepiest guard: {}", best.guard);
+        println!("Sleepiest minute: {}", best.minute);
+        println!("Product: {}", best.product());
+
+        for (rank, r) in rankings
+            .iter()
+            .enumerate()
+            .skip(1)
+            .take(top.saturating_sub(1))
+        {
+            println!(
+                "  #{}: guard {}, minute {}, score {}",
+                rank + 1,
+                r.guard,
+                r.minute,
+                r.score
+            );
+        }
+    }
+
+    if args.iter().any(|a| a == "--csv") {
+        export::write_csv(&mut fs::File::create("guards.csv")?, &timeline)?;
+    }
+    if args.iter().any(|a| a == "--json") {
+        export::write_json(&mut fs::File::create("guards.json")?, &timeline)?;
+    }
+    if args.iter().any(|a| a == "--chart") {
+        print!("{}", timeline.asleep_chart());
+    }
+    if args.iter().any(|a| a == "--stats") {
+        for guard in timeline.guards() {
+            println!(
+                "Guard #{}: {} minutes asleep",
+                guard,
+                timeline.total_sleep(guard)
+            );
+            for (date, minutes) in timeline.sleep_per_day(guard) {
+                println!("  {}: {}", date, minutes);
             }
         }
+        if let Some((guard, nap)) = timeline.longest_nap() {
+            println!(
+                "Longest nap: guard #{}, {} to {} ({} minutes)",
+                guard,
+                nap.from,
+                nap.until,
+                nap.minutes()
+            );
+        }
     }
 
-    let (guard, minute) = sleepy_times
-        .iter()
-        .max_by_key::<u32, _>(|(_, vs)| vs.iter().sum())
-        .map(|(&k, vs)| (k, vs.iter().enumerate().max_by_key(|(_, &v)| v).unwrap().0))
-        .ok_or(Error::Record)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    println!("=== Strategy #1");
-    println!("Sleepiest guard: {}", guard);
-    println!("Sleepiest minute: {}", minute);
-    println!("Product: {}", guard * minute as u32);
+    use chrono::NaiveTime;
+    use timeline::Timeline;
 
-    let (guard, minute) = sleepy_times
-        .iter()
-        .max_by_key(|(_, vs)| vs.iter().max())
-        .map(|(&k, vs)| (k, vs.iter().enumerate().max_by_key(|(_, &v)| v).unwrap().0))
-        .ok_or(Error::Record)?;
+    const EXAMPLE: &str = "[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-01 00:30] falls asleep
+[1518-11-01 00:55] wakes up
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:40] falls asleep
+[1518-11-02 00:50] wakes up
+[1518-11-03 00:05] Guard #10 begins shift
+[1518-11-03 00:24] falls asleep
+[1518-11-03 00:29] wakes up
+[1518-11-04 00:02] Guard #99 begins shift
+[1518-11-04 00:36] falls asleep
+[1518-11-04 00:46] wakes up
+[1518-11-05 00:03] Guard #99 begins shift
+[1518-11-05 00:45] falls asleep
+[1518-11-05 00:55] wakes up";
 
-    println!("=== Strategy #2");
-    println!("Sleepiest guard: {}", guard);
-    println!("Sleepiest minute: {}", minute);
-    println!("Product: {}", guard * minute as u32);
+    fn timeline(input: &str) -> Timeline {
+        let records: Vec<Record> = input.lines().map(|l| l.parse().unwrap()).collect();
+        Timeline::from_records(&records)
+    }
 
-    Ok(())
+    #[test]
+    fn example() {
+        let timeline = timeline(EXAMPLE);
+        let midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+
+        assert_eq!(timeline.total_sleep(10), 50);
+        assert_eq!(timeline.histogram(10, midnight, 60)[24], 2);
+        assert_eq!(timeline.histogram(99, midnight, 60)[45], 3);
+        assert_eq!(timeline.longest_nap().unwrap().1.minutes(), 25);
+
+        let chart = timeline.asleep_chart();
+        let mut rows = chart.lines().skip(3);
+        assert_eq!(
+            rows.next(),
+            Some("11-01  #10    .....####################.....#########################.....")
+        );
+        assert_eq!(
+            rows.next(),
+            Some("11-02  #99    ........................................##########..........")
+        );
+    }
+
+    #[test]
+    fn strategies() {
+        let timeline = timeline(EXAMPLE);
+
+        let s1 = strategy::strategy1(&timeline);
+        assert_eq!((s1[0].guard, s1[0].minute, s1[0].score), (10, 24, 50));
+        assert_eq!(s1[0].product(), 240);
+        assert_eq!(s1[1].guard, 99);
+
+        let s2 = strategy::strategy2(&timeline);
+        assert_eq!((s2[0].guard, s2[0].minute, s2[0].score), (99, 45, 3));
+        assert_eq!(s2[0].product(), 4455);
+
+        let mut csv = Vec::new();
+        export::write_csv(&mut csv, &timeline).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let row: Vec<_> = csv.lines().nth(1).unwrap().split(',').collect();
+        assert_eq!(row.len(), 62);
+        assert_eq!(&row[..2], &["10", "50"]);
+        assert_eq!(row[2 + 24], "2");
+    }
+
+    #[test]
+    fn validation() {
+        let records: Vec<Record> = "[1518-11-01 00:02] falls asleep
+[1518-11-01 00:04] wakes up
+[1518-11-01 23:58] Guard #10 begins shift
+[1518-11-02 00:05] falls asleep
+[1518-11-02 00:07] falls asleep
+[1518-11-02 00:25] wakes up
+[1518-11-02 00:30] wakes up
+[1518-11-02 00:40] falls asleep
+[1518-11-03 00:00] Guard #99 begins shift
+[1518-11-03 00:45] falls asleep"
+            .lines()
+            .enumerate()
+            .map(|(idx, l)| {
+                let mut rec: Record = l.parse().unwrap();
+                rec.line = idx + 1;
+                rec
+            })
+            .collect();
+
+        let (repaired, issues) = validate::validate(&records);
+        let found: Vec<_> = issues.iter().map(|i| (i.line, i.anomaly)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, validate::Anomaly::SleepBeforeShift),
+                (2, validate::Anomaly::WakeWithoutSleep),
+                (5, validate::Anomaly::AlreadyAsleep),
+                (7, validate::Anomaly::WakeWithoutSleep),
+                (8, validate::Anomaly::AsleepAtShiftEnd),
+                (10, validate::Anomaly::AsleepAtShiftEnd),
+            ]
+        );
+
+        let timeline = Timeline::from_records(&repaired);
+        assert_eq!(timeline.total_sleep(10), 20 + 20);
+        assert_eq!(timeline.total_sleep(99), 15);
+        assert!(validate::validate(&repaired).1.is_empty());
+    }
+
+    #[test]
+    fn nap_across_midnight() {
+        let timeline = timeline(
+            "[1518-11-01 23:50] Guard #7 begins shift
+[1518-11-01 23:58] falls asleep
+[1518-11-02 00:03] wakes up",
+        );
+
+        let eleven = NaiveTime::from_hms_opt(23, 0, 0).unwrap();
+        let hist = timeline.histogram(7, eleven, 120);
+        assert_eq!(hist[57..64], [0, 1, 1, 1, 1, 1, 0]);
+
+        let days: Vec<_> = timeline.sleep_per_day(7).into_values().collect();
+        assert_eq!(days, vec![2, 3]);
+    }
 }
@@ -0,0 +1,61 @@
+use chrono::NaiveTime;
+
+use std::cmp::Reverse;
+
+use timeline::Timeline;
+
+/// A guard's sleepiest minute of the midnight hour, and how they rank under a strategy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ranking {
+    pub guard: u32,
+    pub minute: usize,
+    /// What the strategy ranks by, either total minutes asleep, or how often the guard slept
+    /// during their sleepiest minute
+    pub score: u32,
+}
+
+impl Ranking {
+    pub fn product(&self) -> u32 {
+        self.guard * self.minute as u32
+    }
+}
+
+pub fn midnight_hour(timeline: &Timeline, guard: u32) -> Vec<u32> {
+    timeline.histogram(guard, NaiveTime::from_hms_opt(0, 0, 0).unwrap(), 60)
+}
+
+fn rank<F>(timeline: &Timeline, score: F) -> Vec<Ranking>
+where
+    F: Fn(u32, &[u32]) -> u32,
+{
+    let mut rankings: Vec<Ranking> = timeline
+        .guards()
+        .into_iter()
+        .map(|guard| {
+            let hist = midnight_hour(timeline, guard);
+            // Ties go to the earliest minute
+            let (minute, _) = hist
+                .iter()
+                .enumerate()
+                .max_by_key(|(m, &v)| (v, Reverse(*m)))
+                .unwrap();
+            Ranking {
+                guard,
+                minute,
+                score: score(guard, &hist),
+            }
+        })
+        .collect();
+    rankings.sort_by_key(|r| (Reverse(r.score), r.guard));
+    rankings
+}
+
+/// Guards ranked by the total time they spent asleep.
+pub fn strategy1(timeline: &Timeline) -> Vec<Ranking> {
+    rank(timeline, |guard, _| timeline.total_sleep(guard) as u32)
+}
+
+/// Guards ranked by how often they were asleep during the same minute.
+pub fn strategy2(timeline: &Timeline) -> Vec<Ranking> {
+    rank(timeline, |_, hist| hist.iter().cloned().max().unwrap_or(0))
+}
@@ -0,0 +1,160 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use {Event, Record};
+
+/// A period of sleep, from the minute the guard falls asleep until the minute they wake up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nap {
+    pub from: NaiveDateTime,
+    pub until: NaiveDateTime,
+}
+
+impl Nap {
+    pub fn minutes(&self) -> i64 {
+        (self.until - self.from).num_minutes()
+    }
+
+    /// Every minute during which the guard is asleep.
+    pub fn iter_minutes(&self) -> impl Iterator<Item = NaiveDateTime> {
+        let from = self.from;
+        (0..self.minutes()).map(move |m| from + Duration::minutes(m))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Shift {
+    pub guard: u32,
+    pub begin: NaiveDateTime,
+    pub naps: Vec<Nap>,
+}
+
+impl Shift {
+    /// The date of the midnight hour the shift is guarding, which is the next day for shifts
+    /// that begin before midnight.
+    pub fn date(&self) -> NaiveDate {
+        if self.begin.hour() >= 12 {
+            self.begin.date() + Duration::days(1)
+        } else {
+            self.begin.date()
+        }
+    }
+
+    fn asleep_at(&self, t: NaiveDateTime) -> bool {
+        self.naps.iter().any(|n| n.from <= t && t < n.until)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Timeline {
+    pub shifts: Vec<Shift>,
+}
+
+impl Timeline {
+    /// Builds the timeline from records sorted by time. Events before the first shift begins,
+    /// and wake-ups of guards that are not asleep, are ignored.
+    pub fn from_records(records: &[Record]) -> Timeline {
+        let mut timeline = Timeline::default();
+        let mut asleep = None;
+        for rec in records {
+            match rec.event {
+                Event::BeginShift { id } => {
+                    timeline.shifts.push(Shift {
+                        guard: id,
+                        begin: rec.datetime,
+                        naps: Vec::new(),
+                    });
+                    asleep = None;
+                }
+                Event::FallAsleep => asleep = Some(rec.datetime),
+                Event::WakeUp => {
+                    if let (Some(from), Some(shift)) = (asleep.take(), timeline.shifts.last_mut()) {
+                        shift.naps.push(Nap {
+                            from,
+                            until: rec.datetime,
+                        });
+                    }
+                }
+            }
+        }
+        timeline
+    }
+
+    pub fn guards(&self) -> BTreeSet<u32> {
+        self.shifts.iter().map(|s| s.guard).collect()
+    }
+
+    pub fn naps(&self, guard: u32) -> impl Iterator<Item = &Nap> {
+        self.shifts
+            .iter()
+            .filter(move |s| s.guard == guard)
+            .flat_map(|s| s.naps.iter())
+    }
+
+    pub fn total_sleep(&self, guard: u32) -> i64 {
+        self.naps(guard).map(|n| n.minutes()).sum()
+    }
+
+    /// Minutes asleep per calendar day. Naps across midnight count towards both days.
+    pub fn sleep_per_day(&self, guard: u32) -> BTreeMap<NaiveDate, i64> {
+        let mut days = BTreeMap::new();
+        for t in self.naps(guard).flat_map(|n| n.iter_minutes()) {
+            *days.entry(t.date()).or_insert(0) += 1;
+        }
+        days
+    }
+
+    pub fn longest_nap(&self) -> Option<(u32, &Nap)> {
+        self.shifts
+            .iter()
+            .flat_map(|s| s.naps.iter().map(move |n| (s.guard, n)))
+            .max_by_key(|(_, n)| n.minutes())
+    }
+
+    /// How often the guard was asleep during each of the `len` minutes of the day following
+    /// `start`. The window may extend past midnight.
+    pub fn histogram(&self, guard: u32, start: NaiveTime, len: usize) -> Vec<u32> {
+        let mut hist = vec![0; len];
+        for t in self.naps(guard).flat_map(|n| n.iter_minutes()) {
+            let offset = (t.time() - start).num_minutes().rem_euclid(24 * 60) as usize;
+            if offset < len {
+                hist[offset] += 1;
+            }
+        }
+        hist
+    }
+
+    /// Draws the midnight hour of every shift, like the puzzle text does.
+    pub fn asleep_chart(&self) -> String {
+        let mut chart = String::new();
+        let minutes: String = (0..60).map(|m| format!("{}", m % 10)).collect();
+        let tens: String = (0..60).map(|m| format!("{}", m / 10)).collect();
+        writeln!(chart, "Date   ID     Minute").unwrap();
+        writeln!(chart, "              {}", tens).unwrap();
+        writeln!(chart, "              {}", minutes).unwrap();
+
+        for shift in &self.shifts {
+            let midnight = shift.date().and_hms_opt(0, 0, 0).unwrap();
+            let row: String = (0..60)
+                .map(|m| {
+                    if shift.asleep_at(midnight + Duration::minutes(m)) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            writeln!(
+                chart,
+                "{}  {:<5}  {}",
+                shift.date().format("%m-%d"),
+                format!("#{}", shift.guard),
+                row
+            )
+            .unwrap();
+        }
+        chart
+    }
+}
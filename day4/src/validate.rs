@@ -0,0 +1,104 @@
+use chrono::{Duration, NaiveDateTime, Timelike};
+
+use std::fmt;
+
+use {Event, Record};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anomaly {
+    /// A guard falls asleep before any shift has begun
+    SleepBeforeShift,
+    /// A guard falls asleep while already asleep
+    AlreadyAsleep,
+    /// A guard wakes up without having fallen asleep
+    WakeWithoutSleep,
+    /// A guard is still asleep when the next shift begins, or when the log ends
+    AsleepAtShiftEnd,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub line: usize,
+    pub datetime: NaiveDateTime,
+    pub anomaly: Anomaly,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {} [{}]: {:?}",
+            self.line,
+            self.datetime.format("%Y-%m-%d %H:%M"),
+            self.anomaly
+        )
+    }
+}
+
+// When a nap that never ends is cut off: at the end of the midnight hour the guard sleeps in
+fn end_of_nap(from: NaiveDateTime) -> NaiveDateTime {
+    let hour = from.date().and_hms_opt(from.hour(), 0, 0).unwrap();
+    hour + Duration::hours(1)
+}
+
+/// Runs the sorted records through the guard state machine and reports every anomaly.
+///
+/// Also returns the records with all anomalies repaired: stray events are dropped, and naps
+/// that never end are cut off at the next shift change, or at the end of their hour.
+pub fn validate(records: &[Record]) -> (Vec<Record>, Vec<Issue>) {
+    let mut repaired = Vec::with_capacity(records.len());
+    let mut issues = Vec::new();
+    let mut issue = |rec: &Record, anomaly| {
+        issues.push(Issue {
+            line: rec.line,
+            datetime: rec.datetime,
+            anomaly,
+        })
+    };
+
+    let mut on_duty = false;
+    let mut asleep: Option<&Record> = None;
+    for rec in records {
+        match rec.event {
+            Event::BeginShift { .. } => {
+                if let Some(nap) = asleep.take() {
+                    issue(nap, Anomaly::AsleepAtShiftEnd);
+                    repaired.push(Record {
+                        datetime: end_of_nap(nap.datetime).min(rec.datetime),
+                        event: Event::WakeUp,
+                        line: nap.line,
+                    });
+                }
+                on_duty = true;
+            }
+            Event::FallAsleep => {
+                if !on_duty {
+                    issue(rec, Anomaly::SleepBeforeShift);
+                    continue;
+                } else if asleep.is_some() {
+                    issue(rec, Anomaly::AlreadyAsleep);
+                    continue;
+                }
+                asleep = Some(rec);
+            }
+            Event::WakeUp => {
+                if asleep.take().is_none() {
+                    issue(rec, Anomaly::WakeWithoutSleep);
+                    continue;
+                }
+            }
+        }
+        repaired.push(rec.clone());
+    }
+
+    if let Some(nap) = asleep {
+        issue(nap, Anomaly::AsleepAtShiftEnd);
+        repaired.push(Record {
+            datetime: end_of_nap(nap.datetime),
+            event: Event::WakeUp,
+            line: nap.line,
+        });
+    }
+
+    (repaired, issues)
+}
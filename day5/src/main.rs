@@ -1,54 +1,222 @@
+mod rules;
+
+use rules::{Rules, Validation};
+
+use std::env;
+use std::error;
 use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::thread;
 
-fn react(s: &[u8]) -> Vec<u8> {
-    let mut result = s.to_owned();
-    loop {
-        let bounds = &result
-            .windows(2)
-            .enumerate()
-            .filter_map(|(idx, vs)| {
-                if (vs[0] as i32 - vs[1] as i32).abs() == 32 {
-                    Some(idx)
-                } else {
-                    None
-                }
-            }).collect::<Vec<_>>();
-
-        if bounds.is_empty() {
-            break;
+/// Reduces a polymer unit by unit. Whatever has been pushed so far is always fully reacted, so
+/// every new unit can only react with the last remaining one.
+struct Reactor<'a> {
+    rules: &'a Rules,
+    stack: Vec<u8>,
+    // Positions of non-unit bytes, and of whitespace that is only fine if nothing follows it
+    invalid: Vec<usize>,
+    trailing: Vec<usize>,
+    pos: usize,
+}
+
+impl<'a> Reactor<'a> {
+    fn new(rules: &'a Rules) -> Reactor<'a> {
+        Reactor {
+            rules,
+            stack: Vec::new(),
+            invalid: Vec::new(),
+            trailing: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    fn push(&mut self, unit: u8) {
+        let pos = self.pos;
+        self.pos += 1;
+
+        if !self.rules.is_unit(unit) {
+            if unit.is_ascii_whitespace() {
+                self.trailing.push(pos);
+            } else {
+                self.invalid.append(&mut self.trailing);
+                self.invalid.push(pos);
+            }
+            return;
+        }
+        self.invalid.append(&mut self.trailing);
+
+        match self.stack.last() {
+            Some(&last) if self.rules.reacts(last, unit) => {
+                self.stack.pop();
+            }
+            _ => self.stack.push(unit),
+        }
+    }
+
+    fn into_reaction(self, validation: Validation) -> Result<Reaction, rules::Error> {
+        if validation == Validation::Strict && !self.invalid.is_empty() {
+            return Err(rules::Error::InvalidUnits(self.invalid));
         }
+        Ok(Reaction {
+            polymer: self.stack,
+            stripped: self.invalid,
+        })
+    }
+}
 
-        let mut prev = result.len();
-        for b in bounds.iter().rev() {
-            if b + 1 < prev {
-                result.drain(b..&(b + 2));
-                prev = *b;
+/// The fully reacted polymer, and the positions of the bytes that were dropped because they
+/// are not units.
+#[derive(Debug, PartialEq)]
+struct Reaction {
+    polymer: Vec<u8>,
+    stripped: Vec<usize>,
+}
+
+fn react_reader<R: Read>(
+    r: R,
+    rules: &Rules,
+    validation: Validation,
+) -> Result<Reaction, Box<dyn error::Error>> {
+    let mut reader = BufReader::new(r);
+    let mut reactor = Reactor::new(rules);
+    loop {
+        let len = {
+            let buf = reader.fill_buf()?;
+            for &unit in buf {
+                reactor.push(unit);
             }
+            buf.len()
+        };
+        if len == 0 {
+            return Ok(reactor.into_reaction(validation)?);
         }
+        reader.consume(len);
     }
-    result
 }
 
-fn main() -> Result<(), Box<std::error::Error>> {
-    let input = fs::read("input")?;
-
-    let units = react(&input).len();
-    println!("Part 1: units remaining: {}", units);
-
-    let mut min = input.len();
-    for c in 'A' as u8..'Z' as u8 {
-        min = std::cmp::min(
-            min,
-            react(
-                &input
-                    .iter()
-                    .cloned()
-                    .filter(|&d| d != c && d != c + 32)
-                    .collect::<Vec<_>>(),
-            ).len(),
+/// Finds the unit type whose removal leads to the shortest polymer, and that polymer's length.
+/// Types are identified by their smallest unit.
+///
+/// Since every unit reacts with at most one other unit, the order of reactions doesn't matter
+/// and removing units from the fully reacted polymer only allows for further reactions, so it
+/// can be used instead of the original one.
+fn shortest_without_unit(polymer: &[u8], rules: &Rules) -> Option<(u8, usize)> {
+    let types = rules.unit_types();
+    thread::scope(|s| {
+        let handles: Vec<_> = types
+            .iter()
+            .map(|removed| {
+                s.spawn(move || {
+                    let mut reactor = Reactor::new(rules);
+                    for &unit in polymer.iter().filter(|u| !removed.contains(u)) {
+                        reactor.push(unit);
+                    }
+                    (removed[0], reactor.stack.len())
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("reaction thread panicked"))
+            .min_by_key(|&(_, len)| len)
+    })
+}
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    let mut rules = Rules::default();
+    let mut validation = Validation::Strict;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rules" => {
+                let path = args.next().ok_or("--rules needs a pair table file")?;
+                rules = Rules::parse_table(&fs::read_to_string(path)?)?;
+            }
+            "--strip" => validation = Validation::Strip,
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+
+    let Reaction { polymer, stripped } =
+        react_reader(fs::File::open("input")?, &rules, validation)?;
+    if !stripped.is_empty() {
+        eprintln!(
+            "Warning: stripped {} invalid unit(s) at byte positions {}",
+            stripped.len(),
+            rules::join(&stripped)
         );
     }
+    println!("Part 1: units remaining: {}", polymer.len());
+
+    let (_, min) = shortest_without_unit(&polymer, &rules).ok_or("empty polymer")?;
     println!("Part 2: units remaining: {}", min);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn react(s: &[u8], validation: Validation) -> Result<Reaction, rules::Error> {
+        let rules = Rules::default();
+        let mut reactor = Reactor::new(&rules);
+        for &unit in s {
+            reactor.push(unit);
+        }
+        reactor.into_reaction(validation)
+    }
+
+    #[test]
+    fn example() {
+        let rules = Rules::default();
+        let reaction =
+            react_reader(&b"dabAcCaCBAcCcaDA\n"[..], &rules, Validation::Strict).unwrap();
+        let polymer = reaction.polymer;
+        assert_eq!(polymer, b"dabCBAcaDA".to_vec());
+        assert_eq!(shortest_without_unit(&polymer, &rules), Some((b'C', 4)));
+    }
+
+    #[test]
+    fn removes_z() {
+        assert_eq!(
+            shortest_without_unit(b"ZaA", &Rules::default()),
+            Some((b'Z', 0))
+        );
+    }
+
+    #[test]
+    fn validation() {
+        // Not letters, even though they are 32 apart
+        assert_eq!(
+            react(b"a@`A \n", Validation::Strict),
+            Err(rules::Error::InvalidUnits(vec![1, 2]))
+        );
+        assert_eq!(
+            react(b"a@`A \n", Validation::Strip),
+            Ok(Reaction {
+                polymer: vec![],
+                stripped: vec![1, 2]
+            })
+        );
+        assert_eq!(
+            react(b"ab\ncd\n", Validation::Strict),
+            Err(rules::Error::InvalidUnits(vec![2]))
+        );
+    }
+
+    #[test]
+    fn custom_rules() {
+        let rules = Rules::parse_table("ad bc ef").unwrap();
+        let polymer = react_reader(&b"baecdabad"[..], &rules, Validation::Strict)
+            .unwrap()
+            .polymer;
+        assert_eq!(polymer, b"bae".to_vec());
+        assert_eq!(shortest_without_unit(&polymer, &rules), Some((b'a', 2)));
+
+        // With `be` and `ce` as well, reducing `baecdabad` gives `bab`, but without `a` and `d`
+        // it reacts away completely, so the reduced polymer could not be reused
+        assert!(Rules::parse_table("ad bc be ce").is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use std::error;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// Bytes that are not units of the polymer, by position in the input
+    InvalidUnits(Vec<usize>),
+    /// A line of a pair table that is not made of two-unit pairs
+    InvalidRule { line: usize, text: String },
+    /// A unit that would react with more than one other unit
+    AmbiguousUnit { line: usize, unit: u8 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidUnits(positions) => {
+                write!(f, "invalid units at byte positions {}", join(positions))
+            }
+            Error::InvalidRule { line, text } => {
+                write!(f, "invalid reaction rule on line {}: {:?}", line, text)
+            }
+            Error::AmbiguousUnit { line, unit } => write!(
+                f,
+                "unit {:?} on line {} already reacts with another unit",
+                *unit as char, line
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Formats byte positions as a comma-separated list.
+pub fn join(positions: &[usize]) -> String {
+    positions
+        .iter()
+        .map(|pos| pos.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Which bytes are units of a polymer, and which pairs of units annihilate each other.
+///
+/// Every unit reacts with at most one other unit. Otherwise the outcome of a reaction could
+/// depend on the order in which units react, e.g. `abc` with the pairs `ab` and `bc`.
+pub struct Rules {
+    units: [bool; 256],
+    pairs: Vec<bool>,
+}
+
+impl Rules {
+    fn empty() -> Rules {
+        Rules {
+            units: [false; 256],
+            pairs: vec![false; 256 * 256],
+        }
+    }
+
+    /// Builds rules from a list of annihilating pairs. Every unit of a pair is a valid unit.
+    /// Fails with the first unit that is part of two different pairs.
+    pub fn from_pairs<I: IntoIterator<Item = (u8, u8)>>(pairs: I) -> Result<Rules, u8> {
+        let mut rules = Rules::empty();
+        for (a, b) in pairs {
+            rules.add_pair(a, b)?;
+        }
+        Ok(rules)
+    }
+
+    /// Parses a pair table: each whitespace-separated token is a pair of units, e.g. `aA bB`.
+    /// Lines starting with `#` are ignored.
+    pub fn parse_table(s: &str) -> Result<Rules, Error> {
+        let mut rules = Rules::empty();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            for token in line.split_whitespace() {
+                match token.as_bytes() {
+                    &[a, b] => rules
+                        .add_pair(a, b)
+                        .map_err(|unit| Error::AmbiguousUnit { line: i + 1, unit })?,
+                    _ => {
+                        return Err(Error::InvalidRule {
+                            line: i + 1,
+                            text: line.to_string(),
+                        })
+                    }
+                }
+            }
+        }
+        Ok(rules)
+    }
+
+    fn add_pair(&mut self, a: u8, b: u8) -> Result<(), u8> {
+        for &unit in &[a, b] {
+            let partner = if unit == a { b } else { a };
+            if self.is_unit(unit) && !self.reacts(unit, partner) {
+                return Err(unit);
+            }
+        }
+
+        self.units[a as usize] = true;
+        self.units[b as usize] = true;
+        self.pairs[a as usize * 256 + b as usize] = true;
+        self.pairs[b as usize * 256 + a as usize] = true;
+        Ok(())
+    }
+
+    pub fn is_unit(&self, unit: u8) -> bool {
+        self.units[unit as usize]
+    }
+
+    pub fn reacts(&self, lhs: u8, rhs: u8) -> bool {
+        self.pairs[lhs as usize * 256 + rhs as usize]
+    }
+
+    /// Groups the units into types: a unit and the unit it reacts with, if any. Each type is sorted and the types are ordered by their smallest unit.
+    pub fn unit_types(&self) -> Vec<Vec<u8>> {
+        let mut parent: Vec<usize> = (0..256).collect();
+        fn find(parent: &mut [usize], mut x: usize) -> usize {
+            while parent[x] != x {
+                parent[x] = parent[parent[x]];
+                x = parent[x];
+            }
+            x
+        }
+
+        for a in 0..256 {
+            for b in 0..256 {
+                if self.pairs[a * 256 + b] {
+                    let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+                    parent[ra.max(rb)] = ra.min(rb);
+                }
+            }
+        }
+
+        let mut types: Vec<Vec<u8>> = Vec::new();
+        let mut index: [Option<usize>; 256] = [None; 256];
+        for unit in (0..256).filter(|&u| self.units[u]) {
+            let root = find(&mut parent, unit);
+            match index[root] {
+                Some(i) => types[i].push(unit as u8),
+                None => {
+                    index[root] = Some(types.len());
+                    types.push(vec![unit as u8]);
+                }
+            }
+        }
+        types
+    }
+}
+
+impl Default for Rules {
+    /// ASCII letters, where each letter annihilates with its opposite-case counterpart.
+    fn default() -> Rules {
+        Rules::from_pairs((b'a'..=b'z').map(|c| (c, c.to_ascii_uppercase())))
+            .expect("letters react with their counterpart only")
+    }
+}
+
+/// What to do with bytes of the input that are not units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Validation {
+    /// Fail with the positions of all non-unit bytes, except for trailing whitespace
+    Strict,
+    /// Drop non-unit bytes and carry on, reporting their positions
+    Strip,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_rules() {
+        let rules = Rules::default();
+        assert!(rules.reacts(b'a', b'A'));
+        assert!(rules.reacts(b'Z', b'z'));
+        assert!(!rules.reacts(b'a', b'a'));
+        assert!(!rules.reacts(b'@', b'`'));
+        assert!(!rules.is_unit(b'\n'));
+        assert_eq!(rules.unit_types().len(), 26);
+        assert_eq!(rules.unit_types()[0], b"Aa".to_vec());
+    }
+
+    #[test]
+    fn table() {
+        let rules = Rules::parse_table("# custom\nab cc\n\nxy ba\n").unwrap();
+        assert!(rules.reacts(b'b', b'a'));
+        assert!(rules.reacts(b'c', b'c'));
+        assert!(!rules.reacts(b'a', b'c'));
+        assert_eq!(
+            rules.unit_types(),
+            vec![b"ab".to_vec(), b"c".to_vec(), b"xy".to_vec()]
+        );
+
+        assert_eq!(
+            Rules::parse_table("ab\nbc").err(),
+            Some(Error::AmbiguousUnit {
+                line: 2,
+                unit: b'b'
+            })
+        );
+
+        assert_eq!(
+            Rules::parse_table("ab\nabc").err(),
+            Some(Error::InvalidRule {
+                line: 2,
+                text: "abc".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Exact bounds for Voronoi cells under the Euclidean metric, which has no grid-walking argument
+//! like the Manhattan and Chebyshev metrics.
+
+use crate::{Bound, Coord};
+
+fn cross(o: &Coord, a: &Coord, b: &Coord) -> i64 {
+    let (ax, ay) = (i64::from(a.x - o.x), i64::from(a.y - o.y));
+    let (bx, by) = (i64::from(b.x - o.x), i64::from(b.y - o.y));
+    ax * by - ay * bx
+}
+
+// Vertices of the convex hull in counter-clockwise order, without collinear points
+fn hull(sites: &[Coord]) -> Vec<Coord> {
+    let mut points = sites.to_vec();
+    points.sort_by_key(|c| (c.x, c.y));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut hull: Vec<Coord> = Vec::with_capacity(2 * points.len());
+    for pass in 0..2 {
+        let start = hull.len();
+        let iter: Box<dyn Iterator<Item = &Coord>> = if pass == 0 {
+            Box::new(points.iter())
+        } else {
+            Box::new(points.iter().rev())
+        };
+        for p in iter {
+            while hull.len() >= start + 2
+                && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], p) <= 0
+            {
+                hull.pop();
+            }
+            hull.push(p.clone());
+        }
+        hull.pop();
+    }
+    hull
+}
+
+/// Whether the Euclidean Voronoi cell of each site is unbounded. That is exactly the case for
+/// sites on the boundary of the convex hull of all sites, including those in the middle of an
+/// edge.
+pub fn unbounded(sites: &[Coord]) -> Vec<bool> {
+    let hull = hull(sites);
+    if hull.len() < 3 {
+        // All sites lie on a line
+        return vec![true; sites.len()];
+    }
+
+    sites
+        .iter()
+        .map(|s| {
+            (0..hull.len()).any(|i| {
+                let (a, b) = (&hull[i], &hull[(i + 1) % hull.len()]);
+                cross(a, b, s) == 0
+                    && s.x >= a.x.min(b.x)
+                    && s.x <= a.x.max(b.x)
+                    && s.y >= a.y.min(b.y)
+                    && s.y <= a.y.max(b.y)
+            })
+        })
+        .collect()
+}
+
+/// A bound containing the Euclidean Voronoi cell of a site, which has to be bounded.
+///
+/// The cell is the intersection of the half-planes that are closer to the site than to each
+/// other site. Clipping a huge square around the site by all of them leaves the cell as a
+/// polygon, whose corners are then rounded outwards.
+pub fn cell_bound(sites: &[Coord], site: usize) -> Bound {
+    const HUGE: f64 = 1e12;
+
+    let s = (f64::from(sites[site].x), f64::from(sites[site].y));
+    let mut polygon = vec![
+        (s.0 - HUGE, s.1 - HUGE),
+        (s.0 + HUGE, s.1 - HUGE),
+        (s.0 + HUGE, s.1 + HUGE),
+        (s.0 - HUGE, s.1 + HUGE),
+    ];
+
+    for t in sites.iter().filter(|&t| *t != sites[site]) {
+        let t = (f64::from(t.x), f64::from(t.y));
+        // q is at least as close to s as to t iff dot(q, t - s) <= (|t|² - |s|²) / 2
+        let (nx, ny) = (t.0 - s.0, t.1 - s.1);
+        let limit = (t.0 * t.0 + t.1 * t.1 - s.0 * s.0 - s.1 * s.1) / 2.;
+        let side = |p: &(f64, f64)| p.0 * nx + p.1 * ny - limit;
+
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for i in 0..polygon.len() {
+            let (p, q) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+            let (sp, sq) = (side(&p), side(&q));
+            if sp <= 0. {
+                clipped.push(p);
+            }
+            if (sp < 0. && sq > 0.) || (sp > 0. && sq < 0.) {
+                let f = sp / (sp - sq);
+                clipped.push((p.0 + f * (q.0 - p.0), p.1 + f * (q.1 - p.1)));
+            }
+        }
+        polygon = clipped;
+    }
+
+    let (mut min, mut max) = (s, s);
+    for p in &polygon {
+        min = (min.0.min(p.0), min.1.min(p.1));
+        max = (max.0.max(p.0), max.1.max(p.1));
+    }
+    assert!(max.0 - min.0 < HUGE, "cell is unbounded");
+    // One extra point on each side makes up for rounding errors
+    Bound {
+        xmin: min.0.floor() as i32 - 1,
+        xmax: max.0.ceil() as i32 + 1,
+        ymin: min.1.floor() as i32 - 1,
+        ymax: max.1.ceil() as i32 + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coords(cs: &[(i32, i32)]) -> Vec<Coord> {
+        cs.iter().map(|&(x, y)| Coord { x, y }).collect()
+    }
+
+    #[test]
+    fn hull_boundary() {
+        let sites = coords(&[(0, 0), (10, 0), (5, 1), (5, 10), (5, 0), (5, 5), (0, 0)]);
+        assert_eq!(
+            unbounded(&sites),
+            vec![true, true, false, true, true, false, true]
+        );
+        assert_eq!(unbounded(&coords(&[(0, 0), (1, 1), (2, 2)])), vec![true; 3]);
+    }
+
+    #[test]
+    fn bounded_cell() {
+        let sites = coords(&[(0, 0), (10, 0), (5, 1), (5, 10)]);
+        // The cell reaches down to (5, -12), where all three lower sites are equally far, and
+        // spans from (1.5, 5.5) to (8.5, 5.5) at the top
+        let bound = cell_bound(&sites, 2);
+        assert_eq!(
+            (bound.xmin, bound.xmax, bound.ymin, bound.ymax),
+            (0, 10, -13, 7)
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+
+use crate::{Bound, Coord, Metric};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Label {
+    /// The only closest site, by index
+    Site(usize),
+    /// Several sites are equally close
+    Tie,
+}
+
+/// The closest site of every point inside a bound.
+#[derive(Debug)]
+pub struct Labels {
+    bound: Bound,
+    height: usize,
+    cells: Vec<Label>,
+}
+
+impl Labels {
+    /// Labels every point of `bound`, which has to contain all `sites`.
+    ///
+    /// Manhattan and Chebyshev distances are path lengths on the grid with 4 and 8 neighbours,
+    /// so a breadth-first search from all sites at once reaches each point first from its closest
+    /// sites. A point has a unique closest site exactly if all neighbours it is reached from in
+    /// the same step share that site. Other metrics fall back to checking every site.
+    pub fn new(bound: &Bound, sites: &[Coord], metric: Metric) -> Labels {
+        let mut labels = Labels {
+            bound: bound.clone(),
+            height: (bound.ymax - bound.ymin + 1) as usize,
+            cells: vec![Label::Tie; bound.coords().count()],
+        };
+
+        match metric {
+            Metric::Manhattan => labels.search(sites, &[(1, 0), (-1, 0), (0, 1), (0, -1)]),
+            Metric::Chebyshev => labels.search(
+                sites,
+                &[
+                    (1, 0),
+                    (-1, 0),
+                    (0, 1),
+                    (0, -1),
+                    (1, 1),
+                    (1, -1),
+                    (-1, 1),
+                    (-1, -1),
+                ],
+            ),
+            Metric::EuclideanSquared => labels.scan(sites, metric),
+        }
+        labels
+    }
+
+    fn index(&self, c: &Coord) -> Option<usize> {
+        let b = &self.bound;
+        if c.x < b.xmin || c.x > b.xmax || c.y < b.ymin || c.y > b.ymax {
+            return None;
+        }
+        Some((c.x - b.xmin) as usize * self.height + (c.y - b.ymin) as usize)
+    }
+
+    fn search(&mut self, sites: &[Coord], neighbours: &[(i32, i32)]) {
+        let mut dist = vec![u32::MAX; self.cells.len()];
+        let mut queue = VecDeque::new();
+
+        for (i, site) in sites.iter().enumerate() {
+            let idx = self.index(site).expect("site outside of the labeled bound");
+            if dist[idx] == 0 {
+                self.cells[idx] = Label::Tie;
+            } else {
+                dist[idx] = 0;
+                self.cells[idx] = Label::Site(i);
+                queue.push_back(site.clone());
+            }
+        }
+
+        while let Some(c) = queue.pop_front() {
+            let idx = self.index(&c).unwrap();
+            let (d, label) = (dist[idx], self.cells[idx]);
+            for &(dx, dy) in neighbours {
+                let n = Coord {
+                    x: c.x + dx,
+                    y: c.y + dy,
+                };
+                let nidx = match self.index(&n) {
+                    Some(nidx) => nidx,
+                    None => continue,
+                };
+                if dist[nidx] == u32::MAX {
+                    dist[nidx] = d + 1;
+                    self.cells[nidx] = label;
+                    queue.push_back(n);
+                } else if dist[nidx] == d + 1 && self.cells[nidx] != label {
+                    self.cells[nidx] = Label::Tie;
+                }
+            }
+        }
+    }
+
+    fn scan(&mut self, sites: &[Coord], metric: Metric) {
+        for c in self.bound.coords() {
+            let mut best = (i64::MAX, Label::Tie);
+            for (i, site) in sites.iter().enumerate() {
+                let d = metric.distance(&c, site);
+                if d < best.0 {
+                    best = (d, Label::Site(i));
+                } else if d == best.0 {
+                    best.1 = Label::Tie;
+                }
+            }
+            let idx = self.index(&c).unwrap();
+            self.cells[idx] = best.1;
+        }
+    }
+
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (Coord, Label)> + 'a {
+        self.bound.coords().zip(self.cells.iter().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_matches_scan() {
+        let sites: Vec<Coord> = [(0, 0), (7, 2), (3, 9), (5, 5), (9, 9), (2, 4), (5, 5)]
+            .iter()
+            .map(|&(x, y)| Coord { x, y })
+            .collect();
+        let bound = Bound {
+            xmin: -1,
+            xmax: 10,
+            ymin: -2,
+            ymax: 11,
+        };
+
+        for &metric in &[Metric::Manhattan, Metric::Chebyshev] {
+            let labels = Labels::new(&bound, &sites, metric);
+            let mut expected = Labels::new(&bound, &sites, metric);
+            expected.scan(&sites, metric);
+            assert_eq!(labels.cells, expected.cells);
+            let get = |x, y| labels.cells[labels.index(&Coord { x, y }).unwrap()];
+            assert_eq!(get(5, 5), Label::Tie);
+            assert_eq!(get(0, 0), Label::Site(0));
+            assert!(labels.iter().all(|(c, label)| get(c.x, c.y) == label));
+        }
+    }
+}
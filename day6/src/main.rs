@@ -1,11 +1,16 @@
-use std::collections::HashSet;
+mod cells;
+mod label;
+mod render;
+
+use label::{Label, Labels};
+use std::env;
 use std::error::Error;
 use std::fs;
 use std::iter;
 use std::result;
 use std::str::FromStr;
 
-type Result<T> = result::Result<T, Box<Error>>;
+type Result<T> = result::Result<T, Box<dyn Error>>;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Coord {
@@ -13,7 +18,7 @@ struct Coord {
     y: i32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Bound {
     xmin: i32,
     xmax: i32,
@@ -21,29 +26,87 @@ struct Bound {
     ymax: i32,
 }
 
-impl Coord {
-    fn distance(&self, other: &Coord) -> i32 {
-        (self.x - other.x).abs() + (self.y - other.y).abs()
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Metric {
+    Manhattan,
+    Chebyshev,
+    EuclideanSquared,
+}
+
+impl Metric {
+    fn distance(self, a: &Coord, b: &Coord) -> i64 {
+        let dx = i64::from((a.x - b.x).abs());
+        let dy = i64::from((a.y - b.y).abs());
+        match self {
+            Metric::Manhattan => dx + dy,
+            Metric::Chebyshev => dx.max(dy),
+            Metric::EuclideanSquared => dx * dx + dy * dy,
+        }
     }
 
-    fn closest(&self, others: &[Coord]) -> Option<Coord> {
-        let min = others.iter().map(|o| self.distance(o)).min()?;
-        let candidates: Vec<&Coord> = others.iter().filter(|o| self.distance(o) == min).collect();
+    /// Lower bound on the distance between two points that are `d` apart along one axis.
+    fn axis_bound(self, d: i64) -> i64 {
+        match self {
+            Metric::Manhattan | Metric::Chebyshev => d,
+            Metric::EuclideanSquared => d * d,
+        }
+    }
+}
 
-        if candidates.len() == 1 {
-            Some(candidates[0].clone())
-        } else {
-            None
+impl FromStr for Metric {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "manhattan" => Ok(Metric::Manhattan),
+            "chebyshev" => Ok(Metric::Chebyshev),
+            "euclidean" => Ok(Metric::EuclideanSquared),
+            _ => Err(format!("unknown metric: {}", s).into()),
         }
     }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Area {
+    Finite(usize),
+    Infinite,
+}
 
+impl Coord {
     fn on_bound(&self, b: &Bound) -> bool {
         self.x == b.xmin || self.y == b.ymin || self.x == b.xmax || self.y == b.ymax
     }
 }
 
+impl Bound {
+    fn grow(&self, margin: i32) -> Bound {
+        Bound {
+            xmin: self.xmin - margin,
+            xmax: self.xmax + margin,
+            ymin: self.ymin - margin,
+            ymax: self.ymax + margin,
+        }
+    }
+
+    fn union(&self, other: &Bound) -> Bound {
+        Bound {
+            xmin: self.xmin.min(other.xmin),
+            xmax: self.xmax.max(other.xmax),
+            ymin: self.ymin.min(other.ymin),
+            ymax: self.ymax.max(other.ymax),
+        }
+    }
+
+    fn coords(&self) -> impl Iterator<Item = Coord> {
+        let ys = self.ymin..=self.ymax;
+        (self.xmin..=self.xmax)
+            .flat_map(move |x| iter::repeat(x).zip(ys.clone()))
+            .map(|(x, y)| Coord { x, y })
+    }
+}
+
 impl FromStr for Coord {
-    type Err = Box<Error>;
+    type Err = Box<dyn Error>;
 
     fn from_str(s: &str) -> Result<Self> {
         let coords: Vec<_> = s.split(' ').collect();
@@ -59,10 +122,11 @@ impl FromStr for Coord {
 struct Grid {
     coords: Vec<Coord>,
     bound: Bound,
+    metric: Metric,
 }
 
 impl Grid {
-    fn new(coords: &[Coord]) -> Self {
+    fn new(coords: &[Coord], metric: Metric) -> Self {
         let (xs, ys): (Vec<_>, Vec<_>) = coords.iter().map(|p| (p.x, p.y)).unzip();
         Grid {
             coords: coords.to_vec(),
@@ -72,113 +136,293 @@ impl Grid {
                 ymin: *ys.iter().min().unwrap_or(&0),
                 ymax: *ys.iter().max().unwrap_or(&0),
             },
+            metric,
         }
     }
 
-    fn on_edge(&self, p: &Coord) -> bool {
-        p.on_bound(&self.bound)
+    /// Labels the points of `bound`, which has to contain the bounding box of the sites.
+    fn labels_in(&self, bound: &Bound) -> Labels {
+        Labels::new(bound, &self.coords, self.metric)
     }
 
-    fn part1(&self) -> usize {
-        let (edge, center): (Vec<_>, Vec<_>) = (self.bound.xmin..=self.bound.xmax)
-            .flat_map(|x| iter::repeat(x).zip(self.bound.ymin..=self.bound.ymax))
-            .map(|(x, y)| Coord { x, y })
-            .partition(|c| self.on_edge(c));
-
-        let finites: HashSet<Coord> = {
-            let infinites: HashSet<Coord> = edge
-                .iter()
-                .filter_map(|c| {
-                    if let Some(c) = c.closest(&self.coords) {
-                        Some(c)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            self.coords
-                .iter()
-                .filter(|c| !infinites.contains(c))
-                .cloned()
-                .collect()
+    fn total_distance(&self, c: &Coord) -> i64 {
+        self.coords
+            .iter()
+            .map(|coord| self.metric.distance(c, coord))
+            .sum()
+    }
+
+    // Coordinates rotated by 45 degrees, in which the Chebyshev distance is half the Manhattan
+    // distance
+    fn rotated(c: &Coord) -> (i32, i32) {
+        (c.x + c.y, c.x - c.y)
+    }
+
+    // Bounding box of the sites in rotated coordinates, as (umin, umax, vmin, vmax)
+    fn rotated_bound(&self) -> (i32, i32, i32, i32) {
+        let (us, vs): (Vec<_>, Vec<_>) = self.coords.iter().map(Grid::rotated).unzip();
+        (
+            *us.iter().min().unwrap_or(&0),
+            *us.iter().max().unwrap_or(&0),
+            *vs.iter().min().unwrap_or(&0),
+            *vs.iter().max().unwrap_or(&0),
+        )
+    }
+
+    /// A region that contains every finite area, and enough around it to tell which areas are
+    /// infinite.
+    fn area_bound(&self, unbounded: &[bool]) -> Bound {
+        match self.metric {
+            Metric::Manhattan => self.bound.clone(),
+            Metric::Chebyshev => {
+                // Everything within one step of the rotated bounding box
+                let (umin, umax, vmin, vmax) = self.rotated_bound();
+                Bound {
+                    xmin: (umin + vmin).div_euclid(2) - 1,
+                    xmax: (umax + vmax + 1).div_euclid(2) + 1,
+                    ymin: (umin - vmax).div_euclid(2) - 1,
+                    ymax: (umax - vmin + 1).div_euclid(2) + 1,
+                }
+            }
+            Metric::EuclideanSquared => (0..self.coords.len())
+                .filter(|&i| !unbounded[i])
+                .fold(self.bound.clone(), |b, i| {
+                    b.union(&cells::cell_bound(&self.coords, i))
+                }),
+        }
+    }
+
+    // Whether a site that is closest to `c` is closest to every point of a ray starting there
+    fn escapes(&self, c: &Coord, rotated: (i32, i32, i32, i32)) -> bool {
+        match self.metric {
+            Metric::Manhattan => c.on_bound(&self.bound),
+            Metric::Chebyshev => {
+                let (u, v) = Grid::rotated(c);
+                let (umin, umax, vmin, vmax) = rotated;
+                u <= umin || u >= umax || v <= vmin || v >= vmax
+            }
+            Metric::EuclideanSquared => false,
+        }
+    }
+
+    /// The area closest to each site, in the order of the sites.
+    ///
+    /// Under the Manhattan metric, walking away from the bounding box of the sites increases the
+    /// distance to all sites by the same amount. The same holds for the Chebyshev metric when
+    /// walking diagonally away from the bounding box in coordinates rotated by 45 degrees. So a
+    /// site closest to a point on such a bound has an infinite area, and every site with an
+    /// infinite area is closest to such a point. Under the Euclidean metric, exactly the sites on
+    /// the convex hull have an infinite area.
+    fn areas(&self) -> Vec<Area> {
+        let mut unbounded = match self.metric {
+            Metric::EuclideanSquared => cells::unbounded(&self.coords),
+            _ => vec![false; self.coords.len()],
         };
+        let rotated = self.rotated_bound();
+        let labels = self.labels_in(&self.area_bound(&unbounded));
 
-        let closest: Vec<Coord> = center
-            .iter()
-            .filter_map(|c| {
-                if let Some(c) = c.closest(&self.coords) {
-                    if finites.contains(&c) {
-                        Some(c)
-                    } else {
-                        None
-                    }
+        let mut counts = vec![0; self.coords.len()];
+        for (c, label) in labels.iter() {
+            if let Label::Site(i) = label {
+                if self.escapes(&c, rotated) {
+                    unbounded[i] = true;
+                }
+                counts[i] += 1;
+            }
+        }
+
+        counts
+            .into_iter()
+            .zip(unbounded)
+            .map(|(n, unbounded)| {
+                if unbounded {
+                    Area::Infinite
                 } else {
-                    None
+                    Area::Finite(n)
                 }
             })
-            .collect();
+            .collect()
+    }
 
-        let mut largest_area = 0;
-        for c1 in &self.coords {
-            largest_area =
-                std::cmp::max(largest_area, closest.iter().filter(|c2| c1 == *c2).count());
+    fn largest_area(&self) -> Option<usize> {
+        self.areas()
+            .into_iter()
+            .filter_map(|a| match a {
+                Area::Finite(n) => Some(n),
+                Area::Infinite => None,
+            })
+            .max()
+    }
+
+    /// The region that contains every point whose total distance to all sites is below
+    /// `threshold`.
+    ///
+    /// A point that lies `d` beyond the bounding box of the sites along one axis is at least `d`
+    /// away from every site along that axis, so its total distance is at least
+    /// `n * axis_bound(d)` for `n` sites. The margin is the largest `d` for which that lower bound
+    /// is still below the threshold.
+    fn safe_bound(&self, threshold: i64) -> Option<Bound> {
+        let n = self.coords.len() as i64;
+        if n == 0 || threshold <= 0 {
+            return None;
         }
-        largest_area
+
+        let mut margin = 0;
+        while n * self.metric.axis_bound(margin + 1) < threshold {
+            margin += 1;
+        }
+        Some(self.bound.grow(margin as i32))
     }
 
-    fn part2(&self) -> usize {
-        let start = Coord {
-            x: (self.bound.xmax + self.bound.xmin) / 2,
-            y: (self.bound.ymax + self.bound.ymin) / 2,
+    /// Number of points whose total distance to all sites is below `threshold`.
+    fn safe_area(&self, threshold: i64) -> usize {
+        let bound = match self.safe_bound(threshold) {
+            Some(bound) => bound,
+            None => return 0,
         };
 
-        let mut area = 0;
-        for inc in 0.. {
-            let bound = Bound {
-                xmin: start.x - inc,
-                xmax: start.x + inc,
-                ymin: start.y - inc,
-                ymax: start.y + inc,
-            };
-
-            let cs: Vec<_> = (start.x - inc..=start.x + inc)
-                .flat_map(|x| std::iter::repeat(x).zip(start.y - inc..=start.y + inc))
-                .filter_map(|(x, y)| {
-                    let c = Coord { x, y };
-                    if c.on_bound(&bound) {
-                        if self
-                            .coords
-                            .iter()
-                            .map(|coord| c.distance(coord))
-                            .sum::<i32>()
-                            < 10000
-                        {
-                            Some(c)
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            area += cs.len();
-
-            if cs.is_empty() && inc > start.x + 1 {
-                break;
-            }
-        }
-        area
+        bound
+            .coords()
+            .filter(|c| self.total_distance(c) < threshold)
+            .count()
     }
 }
 
 fn main() -> Result<()> {
+    let mut metric = Metric::Manhattan;
+    let mut threshold = 10000;
+    let mut print_areas = false;
+    let mut render = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--metric" => metric = args.next().ok_or("--metric needs a name")?.parse()?,
+            "--threshold" => threshold = args.next().ok_or("--threshold needs a value")?.parse()?,
+            "--areas" => print_areas = true,
+            "--render" => render = true,
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+
     let input = fs::read_to_string("input")?;
     let coords: Vec<Coord> = input.lines().map(|l| l.parse()).collect::<Result<_>>()?;
 
-    let grid = Grid::new(&coords);
-    println!("Part1 area: {}", grid.part1());
-    println!("Part2 area: {}", grid.part2());
+    let grid = Grid::new(&coords, metric);
+    if print_areas {
+        for (c, area) in coords.iter().zip(grid.areas()) {
+            match area {
+                Area::Finite(n) => println!("{}, {}: {}", c.x, c.y, n),
+                Area::Infinite => println!("{}, {}: infinite", c.x, c.y),
+            }
+        }
+    }
+    if render {
+        render::write_voronoi("voronoi.ppm", &grid, threshold)?;
+    }
+    println!(
+        "Part1 area: {}",
+        grid.largest_area().ok_or("no finite area")?
+    );
+    println!("Part2 area: {}", grid.safe_area(threshold));
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Coord> {
+        "1, 1\n1, 6\n8, 3\n3, 4\n5, 5\n8, 9"
+            .lines()
+            .map(|l| l.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn manhattan() {
+        let grid = Grid::new(&example(), Metric::Manhattan);
+        assert_eq!(
+            grid.areas(),
+            vec![
+                Area::Infinite,
+                Area::Infinite,
+                Area::Infinite,
+                Area::Finite(9),
+                Area::Finite(17),
+                Area::Infinite
+            ]
+        );
+        assert_eq!(grid.largest_area(), Some(17));
+        assert_eq!(grid.safe_area(32), 16);
+    }
+
+    #[test]
+    fn safe_bound() {
+        // A single site: the safe region is a diamond, square or disc that reaches past the
+        // bounding box of the sites
+        let site = vec![Coord { x: 0, y: 0 }];
+        assert_eq!(Grid::new(&site, Metric::Manhattan).safe_area(3), 13);
+        assert_eq!(Grid::new(&site, Metric::Chebyshev).safe_area(3), 25);
+        assert_eq!(Grid::new(&site, Metric::EuclideanSquared).safe_area(5), 13);
+
+        for &metric in &[
+            Metric::Manhattan,
+            Metric::Chebyshev,
+            Metric::EuclideanSquared,
+        ] {
+            let grid = Grid::new(&example(), metric);
+            let threshold = 200;
+            let bound = grid.safe_bound(threshold).unwrap();
+            let outside = bound.grow(1);
+            assert!(outside
+                .coords()
+                .filter(|c| c.on_bound(&outside))
+                .all(|c| grid
+                    .coords
+                    .iter()
+                    .map(|s| metric.distance(&c, s))
+                    .sum::<i64>()
+                    >= threshold));
+        }
+    }
+
+    #[test]
+    fn finite_beyond_bound() {
+        // The cell of (5, 1) reaches below the bounding box, but it is only infinite under the
+        // Manhattan metric
+        let sites: Vec<Coord> = [(0, 0), (10, 0), (5, 1), (5, 10)]
+            .iter()
+            .map(|&(x, y)| Coord { x, y })
+            .collect();
+        let window = Bound {
+            xmin: -200,
+            xmax: 200,
+            ymin: -200,
+            ymax: 200,
+        };
+
+        for &metric in &[
+            Metric::Manhattan,
+            Metric::Chebyshev,
+            Metric::EuclideanSquared,
+        ] {
+            let grid = Grid::new(&sites, metric);
+            let mut counts = vec![0; sites.len()];
+            for (_, label) in grid.labels_in(&window).iter() {
+                if let Label::Site(i) = label {
+                    counts[i] += 1;
+                }
+            }
+
+            let areas = grid.areas();
+            assert_eq!(areas[0], Area::Infinite);
+            assert_eq!(areas[1], Area::Infinite);
+            assert_eq!(areas[3], Area::Infinite);
+            match metric {
+                Metric::Manhattan => assert_eq!(areas[2], Area::Infinite),
+                _ => assert_eq!(areas[2], Area::Finite(counts[2])),
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::label::Label;
+use crate::{Area, Grid};
+
+const TIE: [u8; 3] = [128, 128, 128];
+const SITE: [u8; 3] = [0, 0, 0];
+
+// Fraction of the color kept for sites with an infinite area
+const DIM: f64 = 0.35;
+// How far safe points are blended towards white
+const SAFE: f64 = 0.5;
+
+// Spreads hues by the golden angle, so that neighbouring indices get clearly different colors
+fn site_color(i: usize) -> [u8; 3] {
+    let h = (i as f64 * 137.508) % 360. / 60.;
+    let (s, v) = (0.75, 0.95);
+    let c = v * s;
+    let x = c * (1. - (h % 2. - 1.).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.),
+        1 => (x, c, 0.),
+        2 => (0., c, x),
+        3 => (0., x, c),
+        4 => (x, 0., c),
+        _ => (c, 0., x),
+    };
+    let m = v - c;
+    [
+        ((r + m) * 255.) as u8,
+        ((g + m) * 255.) as u8,
+        ((b + m) * 255.) as u8,
+    ]
+}
+
+fn scale(color: [u8; 3], f: f64) -> [u8; 3] {
+    let mut out = color;
+    for c in &mut out {
+        *c = (f64::from(*c) * f) as u8;
+    }
+    out
+}
+
+fn blend(color: [u8; 3], with: [u8; 3], t: f64) -> [u8; 3] {
+    let mut out = color;
+    for (c, w) in out.iter_mut().zip(&with) {
+        *c = (f64::from(*c) * (1. - t) + f64::from(*w) * t) as u8;
+    }
+    out
+}
+
+/// Writes a PPM image of the regions closest to each site. Ties are gray, regions with an
+/// infinite area are dimmed, and points whose total distance to all sites is below `threshold`
+/// are lightened. The image covers the sites and the whole safe region.
+pub fn write_voronoi<P: AsRef<Path>>(path: P, grid: &Grid, threshold: i64) -> io::Result<()> {
+    let bound = match grid.safe_bound(threshold) {
+        Some(safe) => safe.union(&grid.bound),
+        None => grid.bound.clone(),
+    };
+    let width = (bound.xmax - bound.xmin + 1) as usize;
+    let height = (bound.ymax - bound.ymin + 1) as usize;
+
+    let areas = grid.areas();
+    let mut pixels = vec![TIE; width * height];
+    for (c, label) in grid.labels_in(&bound).iter() {
+        let mut color = match label {
+            Label::Site(i) if areas[i] == Area::Infinite => scale(site_color(i), DIM),
+            Label::Site(i) => site_color(i),
+            Label::Tie => TIE,
+        };
+        if grid.total_distance(&c) < threshold {
+            color = blend(color, [255, 255, 255], SAFE);
+        }
+        let idx = (c.y - bound.ymin) as usize * width + (c.x - bound.xmin) as usize;
+        pixels[idx] = color;
+    }
+    for c in &grid.coords {
+        pixels[(c.y - bound.ymin) as usize * width + (c.x - bound.xmin) as usize] = SITE;
+    }
+
+    let mut out = io::BufWriter::new(fs::File::create(path)?);
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    for p in pixels {
+        out.write_all(&p)?;
+    }
+    out.flush()
+}
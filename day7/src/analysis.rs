@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use crate::{Scheduler, SchedulerError, Task};
+
+/// When a task can run if there are enough workers for everything that is runnable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timing {
+    pub task: Task,
+    pub duration: u32,
+    pub earliest_start: u32,
+    pub latest_start: u32,
+}
+
+impl Timing {
+    /// How long the task can be delayed without delaying the whole project.
+    pub fn slack(&self) -> u32 {
+        self.latest_start - self.earliest_start
+    }
+}
+
+#[derive(Debug)]
+pub struct Analysis {
+    /// Timings in the order a single worker would do the tasks
+    pub timings: Vec<Timing>,
+    /// The longest chain of dependent tasks, which no pool size can speed up
+    pub critical_path: Vec<Task>,
+    pub length: u32,
+}
+
+impl Analysis {
+    pub fn new(sched: &Scheduler) -> Result<Analysis, SchedulerError> {
+        let order = sched.order()?;
+        let mut successors: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (task, deps) in &sched.tasks {
+            for dep in deps {
+                successors.entry(dep).or_default().push(task);
+            }
+        }
+
+        let mut timings: Vec<Timing> = Vec::with_capacity(order.len());
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for task in &order {
+            let earliest_start = sched.tasks[task]
+                .iter()
+                .map(|dep| {
+                    let t = &timings[index[dep.as_str()]];
+                    t.earliest_start + t.duration
+                })
+                .max()
+                .unwrap_or(0);
+            index.insert(task.as_str(), timings.len());
+            timings.push(Timing {
+                task: task.clone(),
+                duration: sched.durations.of(task)?,
+                earliest_start,
+                latest_start: 0,
+            });
+        }
+
+        let length = timings
+            .iter()
+            .map(|t| t.earliest_start + t.duration)
+            .max()
+            .unwrap_or(0);
+        for i in (0..timings.len()).rev() {
+            let latest_end = successors
+                .get(timings[i].task.as_str())
+                .into_iter()
+                .flatten()
+                .map(|succ| timings[index[succ]].latest_start)
+                .min()
+                .unwrap_or(length);
+            timings[i].latest_start = latest_end - timings[i].duration;
+        }
+
+        // Follow tasks without slack, each one starting right when the previous one ends
+        let mut critical_path = Vec::new();
+        let mut current = timings
+            .iter()
+            .find(|t| t.slack() == 0 && t.earliest_start == 0);
+        while let Some(t) = current {
+            critical_path.push(t.task.clone());
+            let end = t.earliest_start + t.duration;
+            current = successors
+                .get(t.task.as_str())
+                .into_iter()
+                .flatten()
+                .map(|succ| &timings[index[succ]])
+                .filter(|s| s.slack() == 0 && s.earliest_start == end)
+                .min_by(|a, b| a.task.cmp(&b.task));
+        }
+
+        Ok(Analysis {
+            timings,
+            critical_path,
+            length,
+        })
+    }
+}
+
+/// The time needed by pools of one worker and more, up to the smallest pool that finishes in
+/// `length` seconds, the length of the critical path. More workers than tasks never help, so the
+/// sweep stops there at the latest.
+pub fn sweep(sched: &Scheduler, length: u32) -> Result<Vec<(usize, u32)>, SchedulerError> {
+    let mut results = Vec::new();
+    for workers in 1..=sched.tasks.len() {
+        let makespan = sched.run_with(workers)?.makespan();
+        results.push((workers, makespan));
+        if makespan <= length {
+            break;
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::example;
+
+    #[test]
+    fn critical_path() {
+        let sched = example();
+        let analysis = Analysis::new(&sched).unwrap();
+        assert_eq!(analysis.length, 14);
+        assert_eq!(analysis.critical_path, vec!["C", "F", "E"]);
+
+        let slack: Vec<(&str, u32)> = analysis
+            .timings
+            .iter()
+            .map(|t| (t.task.as_str(), t.slack()))
+            .collect();
+        assert_eq!(
+            slack,
+            vec![("C", 0), ("A", 1), ("B", 3), ("D", 1), ("F", 0), ("E", 0)]
+        );
+
+        assert_eq!(
+            sweep(&sched, analysis.length).unwrap(),
+            vec![(1, 21), (2, 15), (3, 14)]
+        );
+    }
+}
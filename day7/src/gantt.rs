@@ -0,0 +1,170 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::{Schedule, Task};
+
+/// Renders the schedule second by second up to the second everything is done, in the format of
+/// the puzzle description.
+pub fn table(schedule: &Schedule) -> String {
+    let headers: Vec<String> = (1..=schedule.workers)
+        .map(|i| format!("Worker {}", i))
+        .collect();
+    let width = schedule
+        .entries
+        .iter()
+        .map(|e| e.task.len())
+        .chain(headers.iter().map(|h| h.len()))
+        .max()
+        .unwrap_or(0);
+
+    let mut finished: Vec<_> = schedule.entries.iter().collect();
+    finished.sort_by_key(|e| (e.end, e.worker));
+    let separator = if finished.iter().all(|e| e.task.len() == 1) {
+        ""
+    } else {
+        ","
+    };
+
+    let mut out = String::new();
+    let mut line = String::from("Second");
+    for h in &headers {
+        write!(line, "   {:^1$}", h, width).unwrap();
+    }
+    line.push_str("   Done");
+    out.push_str(line.trim_end());
+    out.push('\n');
+
+    for t in 0..=schedule.makespan() {
+        let mut line = format!("{:>4}  ", t);
+        for worker in 0..schedule.workers {
+            let task = schedule
+                .entries
+                .iter()
+                .find(|e| e.worker == worker && e.start <= t && t < e.end)
+                .map_or(".", |e| e.task.as_str());
+            write!(line, "   {:^1$}", task, width).unwrap();
+        }
+        let done: Vec<&str> = finished
+            .iter()
+            .take_while(|e| e.end <= t)
+            .map(|e| e.task.as_str())
+            .collect();
+        write!(line, "   {}", done.join(separator)).unwrap();
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const SCALE: f64 = 4.;
+const LANE: u32 = 30;
+const MARGIN: u32 = 80;
+const MAX_WIDTH: f64 = 2000.;
+
+/// Writes an SVG Gantt chart of the schedule with one lane per worker. Tasks on `critical` are
+/// drawn in red.
+pub fn write_svg<P: AsRef<Path>>(
+    path: P,
+    schedule: &Schedule,
+    critical: &[Task],
+) -> io::Result<()> {
+    let makespan = schedule.makespan().max(1);
+    let scale = SCALE.min(MAX_WIDTH / f64::from(makespan));
+    let width = f64::from(makespan) * scale + f64::from(2 * MARGIN);
+    let height = (schedule.workers as u32 + 1) * LANE + MARGIN;
+
+    let mut out = io::BufWriter::new(fs::File::create(path)?);
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="12">"#,
+        width, height
+    )?;
+    for worker in 0..schedule.workers {
+        let y = (worker as u32 + 1) * LANE;
+        writeln!(
+            out,
+            r#"<text x="4" y="{}">Worker {}</text>"#,
+            y + LANE / 2 + 4,
+            worker + 1
+        )?;
+    }
+    for e in &schedule.entries {
+        let x = f64::from(MARGIN) + f64::from(e.start) * scale;
+        let w = f64::from(e.end - e.start) * scale;
+        let y = (e.worker as u32 + 1) * LANE + 2;
+        let fill = if critical.contains(&e.task) {
+            "#d62728"
+        } else {
+            "#1f77b4"
+        };
+        writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="white"><title>{}: {}-{}</title></rect>"#,
+            x,
+            y,
+            w,
+            LANE - 4,
+            fill,
+            escape(&e.task),
+            e.start,
+            e.end
+        )?;
+        writeln!(
+            out,
+            r#"<text x="{}" y="{}" fill="white" text-anchor="middle">{}</text>"#,
+            x + w / 2.,
+            y + LANE / 2 + 2,
+            escape(&e.task)
+        )?;
+    }
+    let axis = (schedule.workers as u32 + 1) * LANE + 16;
+    writeln!(
+        out,
+        r#"<text x="{}" y="{}">0</text><text x="{}" y="{}" text-anchor="end">{} s</text>"#,
+        MARGIN,
+        axis,
+        f64::from(MARGIN) + f64::from(makespan) * scale,
+        axis,
+        makespan
+    )?;
+    writeln!(out, "</svg>")?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::example;
+
+    #[test]
+    fn puzzle_table() {
+        let expected = "Second   Worker 1   Worker 2   Done
+   0        C          .
+   1        C          .
+   2        C          .
+   3        A          F       C
+   4        B          F       CA
+   5        B          F       CA
+   6        D          F       CAB
+   7        D          F       CAB
+   8        D          F       CAB
+   9        D          .       CABF
+  10        E          .       CABFD
+  11        E          .       CABFD
+  12        E          .       CABFD
+  13        E          .       CABFD
+  14        E          .       CABFD
+  15        .          .       CABFDE
+";
+        assert_eq!(table(&example().run().unwrap()), expected);
+    }
+}
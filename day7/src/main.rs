@@ -1,103 +1,260 @@
+mod analysis;
+mod gantt;
+
 use std::collections::{HashMap, HashSet};
+use std::env;
+use std::error;
+use std::fmt;
 use std::fs;
 
-type Task = char;
+type Task = String;
 
 type Taskset = HashMap<Task, HashSet<Task>>;
 
-#[derive(Debug)]
+type DurationFn = Box<dyn Fn(&str) -> Option<u32>>;
+
+#[derive(Debug, PartialEq)]
 enum SchedulerError {
     NoRunnable,
+    NoDuration(Task),
+    NoWorkers,
+    ParseLine(String),
+}
+
+impl fmt::Display for SchedulerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchedulerError::NoRunnable => {
+                write!(f, "no runnable task, the dependencies are cyclic")
+            }
+            SchedulerError::NoDuration(task) => write!(f, "no duration for task {}", task),
+            SchedulerError::NoWorkers => write!(f, "no workers to run the tasks"),
+            SchedulerError::ParseLine(line) => write!(f, "invalid line: {:?}", line),
+        }
+    }
+}
+
+impl error::Error for SchedulerError {}
+
+/// How long each task takes.
+enum Durations {
+    Fn(DurationFn),
+    Table(HashMap<Task, u32>),
+}
+
+impl Durations {
+    /// Single uppercase letters take `base` seconds plus their position in the alphabet.
+    fn letters(base: u32) -> Self {
+        Durations::Fn(Box::new(move |task| match task.as_bytes() {
+            &[c @ b'A'..=b'Z'] => Some(base + u32::from(c - b'A') + 1),
+            _ => None,
+        }))
+    }
+
+    /// Parses a table with a task id and its duration in seconds on each line.
+    fn parse_table(s: &str) -> Result<Self, SchedulerError> {
+        let mut table = HashMap::new();
+        for line in s.lines().filter(|l| !l.trim().is_empty()) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match (words.len(), words.get(1).and_then(|d| d.parse().ok())) {
+                (2, Some(duration)) => {
+                    table.insert(words[0].to_string(), duration);
+                }
+                _ => return Err(SchedulerError::ParseLine(line.to_string())),
+            }
+        }
+        Ok(Durations::Table(table))
+    }
+
+    fn of(&self, task: &str) -> Result<u32, SchedulerError> {
+        let duration = match self {
+            Durations::Fn(f) => f(task),
+            Durations::Table(table) => table.get(task).cloned(),
+        };
+        duration.ok_or_else(|| SchedulerError::NoDuration(task.to_string()))
+    }
+}
+
+/// A task that `worker` worked on from second `start` until right before second `end`.
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    worker: usize,
+    task: Task,
+    start: u32,
+    end: u32,
 }
 
 #[derive(Debug)]
+struct Schedule {
+    workers: usize,
+    entries: Vec<Entry>,
+}
+
+impl Schedule {
+    fn makespan(&self) -> u32 {
+        self.entries.iter().map(|e| e.end).max().unwrap_or(0)
+    }
+}
+
 struct Scheduler {
     tasks: Taskset,
+    durations: Durations,
+    workers: usize,
 }
 
 impl Scheduler {
     fn new(tasks: Taskset) -> Self {
-        Scheduler { tasks }
+        Scheduler {
+            tasks,
+            durations: Durations::letters(60),
+            workers: 5,
+        }
     }
 
-    fn schedule(&mut self) -> Result<Option<char>, SchedulerError> {
-        if self.tasks.is_empty() {
-            return Ok(None);
-        }
+    fn durations(mut self, durations: Durations) -> Self {
+        self.durations = durations;
+        self
+    }
 
-        let mut runnables: Vec<char> = self
-            .tasks
-            .iter()
-            .filter_map(
-                |(task, deps)| {
-                    if deps.is_empty() {
-                        Some(task)
-                    } else {
-                        None
-                    }
-                },
-            )
-            .cloned()
-            .collect();
-        runnables.sort();
+    fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
 
-        let r = *runnables.get(0).ok_or(SchedulerError::NoRunnable)?;
-        self.tasks.remove(&r).unwrap();
+    /// The order in which a single worker would finish the tasks, taking them one at a time.
+    fn order(&self) -> Result<Vec<Task>, SchedulerError> {
+        let mut pending = Pending::new(&self.tasks);
+        let mut order = Vec::new();
+        while let Some(task) = pending.next()? {
+            pending.finish(&task);
+            order.push(task);
+        }
+        Ok(order)
+    }
 
-        Ok(Some(r))
+    /// Simulates the worker pool. Whenever workers are idle, they take the alphabetically first
+    /// runnable tasks, in the order of the workers.
+    fn run(&self) -> Result<Schedule, SchedulerError> {
+        self.run_with(self.workers)
     }
 
-    fn schedule_workers(&mut self, workers: &mut [Worker]) {
-        for w in workers {
-            if w.is_busy() {
-                continue;
+    fn run_with(&self, num_workers: usize) -> Result<Schedule, SchedulerError> {
+        if num_workers == 0 && !self.tasks.is_empty() {
+            return Err(SchedulerError::NoWorkers);
+        }
+
+        let mut pending = Pending::new(&self.tasks);
+        let mut workers = vec![Worker::new(); num_workers];
+        let mut entries = Vec::new();
+        let mut t = 0;
+        loop {
+            for (i, w) in workers.iter_mut().enumerate() {
+                if w.is_busy() {
+                    continue;
+                }
+                match pending.next_runnable() {
+                    Some(task) => {
+                        let end = t + self.durations.of(&task)?;
+                        entries.push(Entry {
+                            worker: i,
+                            task: task.clone(),
+                            start: t,
+                            end,
+                        });
+                        w.assign(task, end);
+                    }
+                    None => break,
+                }
             }
 
-            if let Ok(Some(task)) = self.schedule() {
-                w.assign(task);
-            } else {
-                break;
+            // Jump to the next time a task finishes
+            t = match workers.iter().filter_map(|w| w.end()).min() {
+                Some(end) => end,
+                None if pending.is_empty() => break,
+                None => return Err(SchedulerError::NoRunnable),
+            };
+            for w in &mut workers {
+                if let Some(task) = w.finish_at(t) {
+                    pending.finish(&task);
+                }
             }
         }
+
+        Ok(Schedule {
+            workers: num_workers,
+            entries,
+        })
+    }
+}
+
+/// Tasks that have not been started yet, with their unfinished dependencies.
+struct Pending {
+    tasks: Taskset,
+}
+
+impl Pending {
+    fn new(tasks: &Taskset) -> Self {
+        Pending {
+            tasks: tasks.clone(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Removes the alphabetically first task without unfinished dependencies.
+    fn next_runnable(&mut self) -> Option<Task> {
+        let r = self
+            .tasks
+            .iter()
+            .filter(|(_, deps)| deps.is_empty())
+            .map(|(task, _)| task)
+            .min()?
+            .clone();
+        self.tasks.remove(&r);
+        Some(r)
+    }
+
+    fn next(&mut self) -> Result<Option<Task>, SchedulerError> {
+        if self.tasks.is_empty() {
+            return Ok(None);
+        }
+        self.next_runnable()
+            .map(Some)
+            .ok_or(SchedulerError::NoRunnable)
     }
 
-    fn finish(&mut self, task: char) {
+    fn finish(&mut self, task: &str) {
         for deps in self.tasks.values_mut() {
-            deps.remove(&task);
+            deps.remove(task);
         }
     }
 }
 
 #[derive(Debug, Clone)]
 struct Worker {
-    task: Option<Task>,
-    time_left: u32,
+    task: Option<(Task, u32)>,
 }
 
 impl Worker {
     fn new() -> Self {
-        Worker {
-            task: None,
-            time_left: 0,
-        }
+        Worker { task: None }
     }
 
-    fn assign(&mut self, task: Task) {
+    fn assign(&mut self, task: Task, end: u32) {
         // Sonst wird gestreikt!
         assert!(!self.is_busy());
-        self.task = Some(task);
-        self.time_left = 60 + task as u32 - 'A' as u32 + 1;
+        self.task = Some((task, end));
     }
 
-    fn work(&mut self) -> Option<char> {
-        assert!(self.time_left > 0);
-        assert!(self.is_busy());
-        if self.time_left > 0 {
-            self.time_left -= 1;
-        }
+    fn end(&self) -> Option<u32> {
+        self.task.as_ref().map(|&(_, end)| end)
+    }
 
-        if self.time_left == 0 {
-            self.task.take()
+    fn finish_at(&mut self, t: u32) -> Option<Task> {
+        if self.end() == Some(t) {
+            self.task.take().map(|(task, _)| task)
         } else {
             None
         }
@@ -108,47 +265,187 @@ impl Worker {
     }
 }
 
-fn main() -> Result<(), Box<std::error::Error>> {
-    let input = fs::read_to_string("input")?;
-
+fn parse_tasks(input: &str) -> Result<Taskset, SchedulerError> {
     let mut tasks = Taskset::new();
     for line in input.lines() {
-        let chars: Vec<char> = line
-            .replace("Step ", "")
-            .replace(" must be finished before step ", "")
-            .replace(" can begin.", "")
-            .chars()
-            .collect();
-        let dep = *chars.get(0).expect("error parsing line");
-        let task = *chars.get(1).expect("error parsing line");
-        tasks.entry(task).or_insert_with(HashSet::new).insert(dep);
-        tasks.entry(dep).or_insert_with(HashSet::new);
-    }
-
-    let mut sched = Scheduler::new(tasks.clone());
-    let mut order = String::new();
-    while let Some(task) = sched.schedule().expect("deadlock?") {
-        order.push(task);
-        sched.finish(task);
-    }
-    println!("Part 1 order: {}", order);
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.len() != 10
+            || words[0] != "Step"
+            || words[2..7] != ["must", "be", "finished", "before", "step"]
+        {
+            return Err(SchedulerError::ParseLine(line.to_string()));
+        }
+        let (dep, task) = (words[1].to_string(), words[7].to_string());
+        tasks.entry(dep.clone()).or_default();
+        tasks.entry(task).or_default().insert(dep);
+    }
+    Ok(tasks)
+}
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    let input = fs::read_to_string("input")?;
+    let tasks = parse_tasks(&input)?;
 
     let mut sched = Scheduler::new(tasks);
-    let mut workers = vec![Worker::new(); 5];
-    let mut t = 0;
-    sched.schedule_workers(&mut workers);
-    while workers.iter().any(|w| w.is_busy()) {
-        for w in &mut workers {
-            if w.is_busy() {
-                if let Some(task) = w.work() {
-                    sched.finish(task);
-                }
+    let mut print_schedule = false;
+    let mut print_gantt = false;
+    let mut print_critical = false;
+    let mut print_sweep = false;
+    let mut svg = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--workers" => {
+                sched = sched.workers(args.next().ok_or("--workers needs a count")?.parse()?)
+            }
+            "--base" => {
+                let base = args.next().ok_or("--base needs a duration")?.parse()?;
+                sched = sched.durations(Durations::letters(base));
+            }
+            "--durations" => {
+                let path = args.next().ok_or("--durations needs a table file")?;
+                sched = sched.durations(Durations::parse_table(&fs::read_to_string(path)?)?);
             }
+            "--schedule" => print_schedule = true,
+            "--gantt" => print_gantt = true,
+            "--critical" => print_critical = true,
+            "--sweep" => print_sweep = true,
+            "--svg" => svg = true,
+            _ => return Err(format!("unknown argument: {}", arg).into()),
         }
-        sched.schedule_workers(&mut workers);
-        t += 1;
     }
-    println!("Part 2 time elapsed: {}", t);
+
+    println!("Part 1 order: {}", sched.order()?.concat());
+
+    let schedule = sched.run()?;
+    if print_schedule {
+        for e in &schedule.entries {
+            println!(
+                "worker {}: {} from {} to {}",
+                e.worker + 1,
+                e.task,
+                e.start,
+                e.end
+            );
+        }
+    }
+    if print_gantt {
+        print!("{}", gantt::table(&schedule));
+    }
+
+    let analysis = analysis::Analysis::new(&sched)?;
+    if print_critical {
+        println!(
+            "Critical path ({} seconds): {}",
+            analysis.length,
+            analysis.critical_path.join(" -> ")
+        );
+        for timing in &analysis.timings {
+            println!(
+                "{}: earliest start {}, latest start {}, slack {}",
+                timing.task,
+                timing.earliest_start,
+                timing.latest_start,
+                timing.slack()
+            );
+        }
+    }
+    if svg {
+        gantt::write_svg("schedule.svg", &schedule, &analysis.critical_path)?;
+    }
+    if print_sweep {
+        let sweep = analysis::sweep(&sched, analysis.length)?;
+        for &(workers, makespan) in &sweep {
+            println!("{} workers: {} seconds", workers, makespan);
+        }
+        let &(workers, makespan) = sweep.last().ok_or("no tasks to schedule")?;
+        println!(
+            "More than {} workers do not reduce the time below {} seconds",
+            workers, makespan
+        );
+    }
+
+    println!("Part 2 time elapsed: {}", schedule.makespan());
 
     Ok(())
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Step C must be finished before step A can begin.
+Step C must be finished before step F can begin.
+Step A must be finished before step B can begin.
+Step A must be finished before step D can begin.
+Step B must be finished before step E can begin.
+Step D must be finished before step E can begin.
+Step F must be finished before step E can begin.";
+
+    pub fn example() -> Scheduler {
+        Scheduler::new(parse_tasks(EXAMPLE).unwrap())
+            .workers(2)
+            .durations(Durations::letters(0))
+    }
+
+    #[test]
+    fn puzzle_example() {
+        let sched = example();
+        assert_eq!(sched.order().unwrap().concat(), "CABDFE");
+
+        let schedule = sched.run().unwrap();
+        assert_eq!(schedule.makespan(), 15);
+        let entry = |worker, task: &str, start, end| Entry {
+            worker,
+            task: task.to_string(),
+            start,
+            end,
+        };
+        assert_eq!(
+            schedule.entries,
+            vec![
+                entry(0, "C", 0, 3),
+                entry(0, "A", 3, 4),
+                entry(1, "F", 3, 9),
+                entry(0, "B", 4, 6),
+                entry(0, "D", 6, 10),
+                entry(0, "E", 10, 15),
+            ]
+        );
+    }
+
+    #[test]
+    fn table() {
+        let tasks = parse_tasks(
+            "Step build must be finished before step test can begin.
+Step fetch must be finished before step build can begin.
+Step fetch must be finished before step docs can begin.",
+        )
+        .unwrap();
+        let durations = Durations::parse_table("fetch 2\nbuild 10\ntest 5\ndocs 20\n").unwrap();
+        let sched = Scheduler::new(tasks).workers(2).durations(durations);
+        assert_eq!(
+            sched.order().unwrap(),
+            vec!["fetch", "build", "docs", "test"]
+        );
+        assert_eq!(sched.run().unwrap().makespan(), 22);
+
+        let sched = sched.durations(Durations::letters(60));
+        assert_eq!(
+            sched.run().unwrap_err(),
+            SchedulerError::NoDuration("fetch".to_string())
+        );
+    }
+
+    #[test]
+    fn cycle() {
+        let tasks = parse_tasks(
+            "Step A must be finished before step B can begin.
+Step B must be finished before step A can begin.",
+        )
+        .unwrap();
+        let sched = Scheduler::new(tasks);
+        assert_eq!(sched.order().unwrap_err(), SchedulerError::NoRunnable);
+        assert_eq!(sched.run().unwrap_err(), SchedulerError::NoRunnable);
+    }
+}
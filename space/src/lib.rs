@@ -0,0 +1,182 @@
+//! Points and axis-aligned boxes in `N`-dimensional space with Manhattan distances, shared by
+//! the days that search through space.
+
+use std::ops::{Index, IndexMut};
+
+/// A point in `N`-dimensional space, with Manhattan distances.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Coord<const N: usize>(pub [i64; N]);
+
+impl<const N: usize> Coord<N> {
+    pub fn origin() -> Self {
+        Coord([0; N])
+    }
+
+    pub fn dist(&self, other: &Coord<N>) -> i64 {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| (a - b).abs())
+            .sum()
+    }
+}
+
+impl<const N: usize> Index<usize> for Coord<N> {
+    type Output = i64;
+
+    fn index(&self, dim: usize) -> &i64 {
+        &self.0[dim]
+    }
+}
+
+impl<const N: usize> IndexMut<usize> for Coord<N> {
+    fn index_mut(&mut self, dim: usize) -> &mut i64 {
+        &mut self.0[dim]
+    }
+}
+
+// Finds point in interval closest to given value
+fn find_closest_1d(min: i64, max: i64, val: i64) -> i64 {
+    val.max(min).min(max)
+}
+
+/// An axis-aligned box, including both `min` and `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox<const N: usize> {
+    pub min: Coord<N>,
+    pub max: Coord<N>,
+}
+
+impl<const N: usize> BoundingBox<N> {
+    /// Smallest box containing all of the given Manhattan balls, given as (center, radius).
+    pub fn from_balls<I>(balls: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = (Coord<N>, i64)>,
+    {
+        balls.into_iter().fold(None, |bb, (c, r)| {
+            let mut bb = bb.unwrap_or(BoundingBox {
+                min: Coord([i64::MAX; N]),
+                max: Coord([i64::MIN; N]),
+            });
+            for dim in 0..N {
+                bb.min[dim] = bb.min[dim].min(c[dim] - r);
+                bb.max[dim] = bb.max[dim].max(c[dim] + r);
+            }
+            Some(bb)
+        })
+    }
+
+    pub fn num_points(&self) -> u64 {
+        (0..N)
+            .map(|dim| (self.max[dim] - self.min[dim] + 1) as u64)
+            .product()
+    }
+
+    /// Length of the longest side of the box, in points.
+    pub fn max_side(&self) -> i64 {
+        (0..N)
+            .map(|dim| self.max[dim] - self.min[dim] + 1)
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Splits the box in half along every dimension that is wider than a single point, into up
+    /// to 2^N children.
+    pub fn split(&self) -> Vec<BoundingBox<N>> {
+        let mut mid = Coord::<N>::origin();
+        let mut splittable = 0usize;
+        for dim in 0..N {
+            mid[dim] = (self.min[dim] + self.max[dim]).div_euclid(2);
+            if self.min[dim] != self.max[dim] {
+                splittable |= 1 << dim;
+            }
+        }
+
+        (0..1usize << N)
+            .filter(|mask| mask & !splittable == 0)
+            .map(|mask| {
+                let mut child = *self;
+                for dim in 0..N {
+                    if mask & (1 << dim) != 0 {
+                        child.min[dim] = mid[dim] + 1;
+                    } else {
+                        child.max[dim] = mid[dim];
+                    }
+                }
+                child
+            })
+            .collect()
+    }
+
+    pub fn find_closest(&self, c: Coord<N>) -> Coord<N> {
+        let mut res = c;
+        for dim in 0..N {
+            res[dim] = find_closest_1d(self.min[dim], self.max[dim], c[dim]);
+        }
+        res
+    }
+
+    pub fn dist(&self, c: Coord<N>) -> i64 {
+        self.find_closest(c).dist(&c)
+    }
+
+    /// Distance from `c` to the farthest point in the box.
+    pub fn max_dist(&self, c: Coord<N>) -> i64 {
+        (0..N)
+            .map(|dim| {
+                (c[dim] - self.min[dim])
+                    .abs()
+                    .max((c[dim] - self.max[dim]).abs())
+            })
+            .sum()
+    }
+
+    /// Whether the box shares at least one point with the Manhattan ball around `c`.
+    pub fn intersects_ball(&self, c: Coord<N>, r: i64) -> bool {
+        self.dist(c) <= r
+    }
+
+    /// Whether the box lies entirely within the Manhattan ball around `c`.
+    pub fn inside_ball(&self, c: Coord<N>, r: i64) -> bool {
+        self.max_dist(c) <= r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split() {
+        let bb = BoundingBox {
+            min: Coord([-3, 0, 5, 1]),
+            max: Coord([-2, 4, 5, 1]),
+        };
+
+        let children = bb.split();
+        assert_eq!(children.len(), 4);
+        assert_eq!(
+            children.iter().map(|c| c.num_points()).sum::<u64>(),
+            bb.num_points()
+        );
+        assert!(children.contains(&BoundingBox {
+            min: Coord([-3, 0, 5, 1]),
+            max: Coord([-3, 2, 5, 1]),
+        }));
+    }
+
+    #[test]
+    fn balls() {
+        let bb = BoundingBox::from_balls(vec![(Coord([0, 0]), 2), (Coord([5, 1]), 1)]).unwrap();
+        assert_eq!(bb.min, Coord([-2, -2]));
+        assert_eq!(bb.max, Coord([6, 2]));
+
+        let unit = BoundingBox {
+            min: Coord([0, 0]),
+            max: Coord([1, 1]),
+        };
+        assert!(unit.intersects_ball(Coord([3, 1]), 2));
+        assert!(!unit.inside_ball(Coord([3, 1]), 2));
+        assert!(unit.inside_ball(Coord([3, 1]), 4));
+    }
+}